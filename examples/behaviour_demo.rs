@@ -0,0 +1,84 @@
+/// Demo of Unity-like scripts (Behaviour) and coroutines driven by the SystemExecutor
+use ecs_hybrid::*;
+
+/// Moves its entity every frame and destroys it once it leaves the arena
+struct Mover {
+    speed: f32,
+}
+
+impl Behaviour for Mover {
+    fn awake(&mut self, entity: &Entity, _scene: &Scene) {
+        println!("  [Mover] awake on entity {}", entity.id);
+    }
+
+    fn start(&mut self, entity: &Entity, _scene: &Scene) {
+        if !entity.has_component::<Transform>() {
+            entity.add_component(Transform::new(0.0, 0.0, 0.0));
+        }
+        println!("  [Mover] start on entity {}", entity.id);
+    }
+
+    fn update(&mut self, entity: &Entity, _scene: &Scene, delta_time: f32) {
+        let x = entity
+            .with_component_mut::<Transform, _>(|t| {
+                t.x += self.speed * delta_time;
+                t.x
+            })
+            .unwrap_or_default();
+        println!("  [Mover] entity {} at x = {:.2}", entity.id, x);
+
+        if x > 2.0 {
+            entity.destroy_deferred();
+        }
+    }
+
+    fn on_destroy(&mut self, entity: &Entity, _scene: &Scene) {
+        println!("  [Mover] on_destroy for entity {}", entity.id);
+    }
+}
+
+/// Waits 2 seconds, then spawns a Mover - using a coroutine instead of a timer field
+struct Spawner;
+
+impl Behaviour for Spawner {
+    fn start(&mut self, entity: &Entity, scene: &Scene) {
+        let mut waited = false;
+        scene.start_coroutine(entity, move |_entity: &Entity, scene: &Scene| {
+            if !waited {
+                waited = true;
+                println!("  [Spawner] waiting 2 seconds...");
+                return Yield::WaitSeconds(2.0);
+            }
+            let spawned = scene.instantiate();
+            spawned.add_behaviour(Mover { speed: 1.0 });
+            println!("  [Spawner] spawned entity {}", spawned.id);
+            Yield::Done
+        });
+    }
+}
+
+fn main() {
+    println!("=== Behaviour Demo ===\n");
+
+    let scene = Scene::new();
+    let player = scene.instantiate();
+    player
+        .add_component(Name::new("Player"))
+        .add_behaviour(Mover { speed: 1.0 });
+
+    let spawner = scene.instantiate();
+    spawner
+        .add_component(Name::new("Spawner"))
+        .add_behaviour(Spawner);
+
+    // Scripts are driven by the executor's run() - no system to add
+    let mut executor = SystemExecutor::new();
+
+    for frame in 1..=8 {
+        println!("Frame {}:", frame);
+        executor.run(&scene, 1.0);
+        scene.apply_commands();
+    }
+
+    println!("\n✓ Demo completed successfully!");
+}
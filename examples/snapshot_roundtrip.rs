@@ -0,0 +1,72 @@
+/// Binary snapshot round trip - save and reload a large world, compare with the text format
+///
+/// Correctness and error cases are covered by `tests/snapshot.rs`.
+use ecs_hybrid::*;
+use std::time::Instant;
+
+const ENTITY_COUNT: u64 = 100_000;
+
+fn build_world() -> World {
+    let mut world = World::new();
+    let root = world.create_entity();
+    world.add_component(root, Name::new("Root"));
+
+    for i in 1..ENTITY_COUNT {
+        let entity = world.create_entity();
+        world.add_component(entity, Transform::new(i as f32, -(i as f32) * 0.5, 0.25));
+        if i % 2 == 0 {
+            world.add_component(entity, Velocity::new(1.0, 0.0, -1.0));
+        }
+        if i % 3 == 0 {
+            world.add_component(entity, Health::new(100.0));
+            world.add_component(entity, Health::new(i as f32));
+        }
+        if i % 10 == 0 {
+            world.add_component(entity, Name::new(format!("Entity \"{}\"", i)));
+            world.set_parent(entity, Some(root));
+        }
+        if i % 100 == 0 {
+            world.set_active(entity, false);
+            world.set_component_enabled::<Transform>(entity, false);
+            world.add_tag(entity, "Sleeping");
+            world.set_layers(entity, LayerMask::layer(4));
+        }
+    }
+    world
+}
+
+fn main() {
+    println!("=== Binary Snapshot Round Trip ===\n");
+
+    let world = build_world();
+    println!("✓ Created {} entities", ENTITY_COUNT);
+
+    let start = Instant::now();
+    let mut bytes = Vec::new();
+    world.write_snapshot(&mut bytes).unwrap();
+    println!(
+        "✓ Wrote binary snapshot: {} KB in {:.1} ms",
+        bytes.len() / 1024,
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+
+    let start = Instant::now();
+    let mut loaded = World::new();
+    let report = loaded.read_snapshot(&mut bytes.as_slice()).unwrap();
+    println!(
+        "✓ Read binary snapshot in {:.1} ms",
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+
+    assert_eq!(loaded.entities().count(), world.entities().count());
+    assert!(report.migrations.is_empty());
+    println!("✓ {} entities loaded back", loaded.entities().count());
+
+    let start = Instant::now();
+    let text = world.save_to_string().unwrap();
+    println!(
+        "\nText scene for comparison: {} KB in {:.1} ms",
+        text.len() / 1024,
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+}
@@ -112,7 +112,8 @@ fn main() {
         let mut collision_checks: Vec<(usize, f32, f32, f32, bool)> = Vec::new();
 
         // First pass: calculate new positions and check collisions (read-only)
-        for (idx, (transform, velocity)) in world.query2::<Transform, Velocity>().enumerate() {
+        for (idx, (transform, velocity)) in world.query2::<Transform, Velocity>().iter().enumerate()
+        {
             let new_x = transform.x + velocity.x * 0.016;
             let new_y = transform.y + velocity.y * 0.016;
             let new_z = transform.z + velocity.z * 0.016;
@@ -182,17 +183,19 @@ fn main() {
 
     let stopped_count = world
         .query::<Transform>()
-        .map(|iter| {
-            iter.filter(|(entity, transform)| {
-                if *entity == obstacle_entity {
-                    return false;
-                }
-                let dx = transform.x - 50.0;
-                let dy = transform.y;
-                let dist = (dx * dx + dy * dy).sqrt();
-                dist < 15.0 // Near obstacle
-            })
-            .count()
+        .map(|query| {
+            query
+                .iter()
+                .filter(|(entity, transform)| {
+                    if *entity == obstacle_entity {
+                        return false;
+                    }
+                    let dx = transform.x - 50.0;
+                    let dy = transform.y;
+                    let dist = (dx * dx + dy * dy).sqrt();
+                    dist < 15.0 // Near obstacle
+                })
+                .count()
         })
         .unwrap_or(0);
 
@@ -1,6 +1,5 @@
 /// Stress test using Unity-style iteration (get_component)
 use ecs_hybrid::*;
-use parking_lot::MappedRwLockReadGuard;
 use std::time::Instant;
 
 #[derive(Debug, Clone)]
@@ -174,17 +173,19 @@ fn main() {
 
     let stopped_count = world
         .query::<Transform>()
-        .map(|iter| {
-            iter.filter(|(entity, transform)| {
-                if *entity == obstacle_entity_id {
-                    return false;
-                }
-                let dx = transform.x - 50.0;
-                let dy = transform.y;
-                let dist = (dx * dx + dy * dy).sqrt();
-                dist < 15.0 // Near obstacle
-            })
-            .count()
+        .map(|query| {
+            query
+                .iter()
+                .filter(|(entity, transform)| {
+                    if *entity == obstacle_entity_id {
+                        return false;
+                    }
+                    let dx = transform.x - 50.0;
+                    let dy = transform.y;
+                    let dist = (dx * dx + dy * dy).sqrt();
+                    dist < 15.0 // Near obstacle
+                })
+                .count()
         })
         .unwrap_or(0);
 
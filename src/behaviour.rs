@@ -0,0 +1,195 @@
+/// Unity-like MonoBehaviour scripts - lifecycle callbacks driven by the Scene every frame
+use crate::game_object::{Entity, Scene};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// Script attached to an Entity - every callback gets a handle to its own Entity and the Scene
+///
+/// Callbacks run without any world lock held, so they can freely use the Entity/Scene API.
+pub trait Behaviour: Send + 'static {
+    /// Called once, the first frame the script is seen
+    fn awake(&mut self, _entity: &Entity, _scene: &Scene) {}
+
+    /// Called once after every new script of the frame has been awoken, before the first update
+    fn start(&mut self, _entity: &Entity, _scene: &Scene) {}
+
+    /// Called every frame
+    fn update(&mut self, _entity: &Entity, _scene: &Scene, _delta_time: f32) {}
+
+    /// Called once when the entity is destroyed or the script is removed
+    fn on_destroy(&mut self, _entity: &Entity, _scene: &Scene) {}
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+struct ScriptSlot {
+    behaviour: Box<dyn Behaviour>,
+    awoken: bool,
+    started: bool,
+    destroyed: bool,
+}
+
+impl ScriptSlot {
+    /// Run `on_destroy` once, and only for scripts that were awoken
+    fn destroy(&mut self, entity: &Entity, scene: &Scene) {
+        if self.awoken && !self.destroyed {
+            self.destroyed = true;
+            self.behaviour.on_destroy(entity, scene);
+        }
+    }
+}
+
+/// Component wrapping a Behaviour - an entity can hold several
+///
+/// The slot is shared with the Scene's script scheduler, which keeps it alive after the
+/// component is removed so `on_destroy` can still run.
+pub struct Script {
+    slot: Arc<Mutex<ScriptSlot>>,
+}
+
+impl Script {
+    pub fn new<B: Behaviour>(behaviour: B) -> Self {
+        Self {
+            slot: Arc::new(Mutex::new(ScriptSlot {
+                behaviour: Box::new(behaviour),
+                awoken: false,
+                started: false,
+                destroyed: false,
+            })),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Scripts of every entity holding a Script component, by entity id - they run in id order
+type ScriptSlots = BTreeMap<u64, Vec<Arc<Mutex<ScriptSlot>>>>;
+
+/// Script scheduler owned by the Scene - calls awake/start once, update every frame and
+/// on_destroy when scripts die
+///
+/// Scripts added during a frame are picked up on the next `Scene::update_scripts`.
+/// Scripts on inactive entities, or whose Script component is disabled, are not awoken,
+/// started or updated until they become active again.
+#[derive(Default)]
+pub(crate) struct ScriptScheduler {
+    tracked: ScriptSlots,
+}
+
+/// Collect the scripts currently attached to entities, and which entities run them
+/// (column read lock only)
+fn collect_scripts(scene: &Scene) -> (ScriptSlots, HashSet<u64>) {
+    let world = scene.world();
+    let world = world.read_recursive();
+    let Some(storage) = world.read_storage::<Script>() else {
+        return Default::default();
+    };
+
+    let live: ScriptSlots = storage
+        .iter_all()
+        .map(|(entity, scripts)| (entity, scripts.iter().map(|s| s.slot.clone()).collect()))
+        .collect();
+    let active = live
+        .keys()
+        .copied()
+        .filter(|&entity| world.is_active(entity) && storage.is_enabled(entity))
+        .collect();
+    (live, active)
+}
+
+/// Run one frame of script lifecycles
+pub(crate) fn update_scripts(scene: &Scene, delta_time: f32) {
+    destroy_pending(scene);
+
+    // Unlocked while callbacks run, so they can use the whole Scene API
+    let tracked = std::mem::take(&mut scene.scripts().lock().tracked);
+    let (live, active) = collect_scripts(scene);
+
+    // on_destroy for scripts removed since the last frame - their entity is still alive
+    for (&id, slots) in &tracked {
+        let remaining = live.get(&id);
+        for slot in slots {
+            let alive = remaining.is_some_and(|r| r.iter().any(|s| Arc::ptr_eq(s, slot)));
+            if !alive {
+                slot.lock().destroy(&scene.get_entity(id), scene);
+            }
+        }
+    }
+
+    // awake for every new script, then start, so starts can rely on all awakes having run
+    let new_slots: Vec<(u64, &Arc<Mutex<ScriptSlot>>)> = live
+        .iter()
+        .filter(|(id, _)| active.contains(id))
+        .flat_map(|(&id, slots)| slots.iter().map(move |slot| (id, slot)))
+        .filter(|(_, slot)| !slot.lock().started)
+        .collect();
+    for (id, slot) in &new_slots {
+        let mut slot = slot.lock();
+        if !slot.awoken {
+            slot.awoken = true;
+            slot.behaviour.awake(&scene.get_entity(*id), scene);
+        }
+    }
+    for (id, slot) in &new_slots {
+        let mut slot = slot.lock();
+        slot.started = true;
+        slot.behaviour.start(&scene.get_entity(*id), scene);
+    }
+
+    for (&id, slots) in live.iter().filter(|(id, _)| active.contains(id)) {
+        let entity = scene.get_entity(id);
+        for slot in slots {
+            let mut slot = slot.lock();
+            if !slot.destroyed {
+                slot.behaviour.update(&entity, scene, delta_time);
+            }
+        }
+    }
+
+    scene.scripts().lock().tracked = live;
+}
+
+/// Finish destroying the entities `World::destroy_entity` queued because they hold scripts:
+/// `on_destroy` runs first, while every component of the entity is still there
+///
+/// A script that was never awoken (e.g. created and destroyed within one frame) is awoken
+/// first, so every `on_destroy` follows an `awake`; inactive ones get neither, like Unity.
+pub(crate) fn destroy_pending(scene: &Scene) {
+    loop {
+        let pending = scene.world().write().take_pending_destroy();
+        if pending.is_empty() {
+            return;
+        }
+        // Every on_destroy of the batch runs first, so parents and children destroyed
+        // together can still see each other
+        let scripts = {
+            let world = scene.world();
+            let world = world.read_recursive();
+            let mut scripts = Vec::new();
+            if let Some(storage) = world.read_storage::<Script>() {
+                for &id in pending.iter().filter(|&&id| world.contains_entity(id)) {
+                    let active = world.is_active(id) && storage.is_enabled(id);
+                    for script in storage.get_slice(id).unwrap_or_default() {
+                        scripts.push((id, active, script.slot.clone()));
+                    }
+                }
+            }
+            scripts
+        };
+        for (id, active, slot) in scripts {
+            let entity = scene.get_entity(id);
+            let mut slot = slot.lock();
+            if active && !slot.awoken {
+                slot.awoken = true;
+                slot.behaviour.awake(&entity, scene);
+            }
+            slot.destroy(&entity, scene);
+        }
+        let world = scene.world();
+        let mut world = world.write();
+        for &id in pending.iter().rev() {
+            world.destroy_entity_now(id);
+        }
+    }
+}
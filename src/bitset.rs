@@ -0,0 +1,58 @@
+/// Dense bitset indexed by entity id
+#[derive(Default, Clone)]
+pub(crate) struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    /// Set a bit - returns false if it was already set
+    pub(crate) fn insert(&mut self, index: u64) -> bool {
+        let (word, bit) = ((index / 64) as usize, index % 64);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let was_set = self.words[word] & (1 << bit) != 0;
+        self.words[word] |= 1 << bit;
+        if !was_set {
+            self.len += 1;
+        }
+        !was_set
+    }
+
+    /// Clear a bit - returns false if it was not set
+    pub(crate) fn remove(&mut self, index: u64) -> bool {
+        let (word, bit) = ((index / 64) as usize, index % 64);
+        match self.words.get_mut(word) {
+            Some(w) if *w & (1 << bit) != 0 => {
+                *w &= !(1 << bit);
+                self.len -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn contains(&self, index: u64) -> bool {
+        self.words
+            .get((index / 64) as usize)
+            .is_some_and(|w| w & (1 << (index % 64)) != 0)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Set bits in ascending order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| **w != 0)
+            .flat_map(|(i, &w)| {
+                (0..64)
+                    .filter(move |bit| w & (1 << bit) != 0)
+                    .map(move |bit| i as u64 * 64 + bit)
+            })
+    }
+}
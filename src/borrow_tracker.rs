@@ -0,0 +1,123 @@
+/// Runtime borrow tracking shared across every handle and thread
+use parking_lot::{Mutex, RwLock};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Tracker state value meaning "borrowed mutably"
+const WRITE_BORROWED: usize = usize::MAX;
+
+/// Runtime borrow tracking for detecting deadlocks at runtime
+///
+/// One tracker exists per (entity, component type) and lives in the `World`, so two
+/// `Entity` handles to the same entity (or clones sent to other threads) see the same state.
+/// The state is `0` when free, the number of readers when borrowed immutably, or
+/// `WRITE_BORROWED` when borrowed mutably.
+#[derive(Default)]
+pub(crate) struct BorrowTracker {
+    state: AtomicUsize,
+}
+
+impl BorrowTracker {
+    pub(crate) fn borrow_read(self: &Arc<Self>) -> BorrowGuard {
+        let mut current = self.state.load(Ordering::Acquire);
+        loop {
+            if current == WRITE_BORROWED {
+                panic!(
+                    "Cannot borrow component immutably: already borrowed mutably (1 active write borrows)"
+                );
+            }
+            match self.state.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        BorrowGuard {
+            tracker: self.clone(),
+            is_write: false,
+        }
+    }
+
+    pub(crate) fn borrow_write(self: &Arc<Self>) -> BorrowGuard {
+        if let Err(actual) =
+            self.state
+                .compare_exchange(0, WRITE_BORROWED, Ordering::AcqRel, Ordering::Acquire)
+        {
+            if actual == WRITE_BORROWED {
+                panic!(
+                    "Cannot borrow component mutably: already borrowed mutably (1 active write borrows)"
+                );
+            }
+            panic!(
+                "Cannot borrow component mutably: already borrowed immutably ({} active read borrows)",
+                actual
+            );
+        }
+        BorrowGuard {
+            tracker: self.clone(),
+            is_write: true,
+        }
+    }
+}
+
+/// Releases a borrow on drop
+pub(crate) struct BorrowGuard {
+    tracker: Arc<BorrowTracker>,
+    is_write: bool,
+}
+
+impl Drop for BorrowGuard {
+    fn drop(&mut self) {
+        if self.is_write {
+            self.tracker.state.store(0, Ordering::Release);
+        } else {
+            self.tracker.state.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Trackers of one component type, keyed by entity
+type ColumnTrackers = Mutex<HashMap<u64, Arc<BorrowTracker>>>;
+
+/// All borrow trackers of a world, sharded by component type
+///
+/// Like the component columns, each type has its own lock, so borrows of different
+/// component types never contend; the outer map is only write-locked the first time a
+/// type is borrowed.
+#[derive(Default)]
+pub(crate) struct BorrowTrackers {
+    columns: RwLock<HashMap<TypeId, ColumnTrackers>>,
+}
+
+impl BorrowTrackers {
+    /// Get (or lazily create) the tracker for a component type on an entity
+    pub(crate) fn get<T: 'static>(&self, entity: u64) -> Arc<BorrowTracker> {
+        let type_id = TypeId::of::<T>();
+        if let Some(column) = self.columns.read().get(&type_id) {
+            return column.lock().entry(entity).or_default().clone();
+        }
+        self.columns
+            .write()
+            .entry(type_id)
+            .or_default()
+            .lock()
+            .entry(entity)
+            .or_default()
+            .clone()
+    }
+
+    /// Forget every tracker of a destroyed entity
+    pub(crate) fn remove_entity(&self, entity: u64) {
+        for column in self.columns.read().values() {
+            column.lock().remove(&entity);
+        }
+    }
+}
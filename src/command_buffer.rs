@@ -1,39 +1,89 @@
 /// Command buffer - deferred operations for thread-safe entity manipulation
 /// This solves the "inconsistent state" problem mentioned in the conversation
-use crate::ecs_core::World;
+use crate::ecs_core::{EntityAllocator, World};
+use crate::prefab::{Prefab, PrefabHandle};
+use parking_lot::Mutex;
+
+/// Deferred operation applied to a single entity
+pub type EntityCommand = Box<dyn FnOnce(&mut World, u64) + Send>;
 
 /// Commands that can be deferred and executed later
 pub enum Command {
     CreateEntity(Box<dyn FnOnce(&mut World) -> u64 + Send>),
-    AddComponent(u64, Box<dyn FnOnce(&mut World, u64) + Send>),
-    RemoveComponent(u64, Box<dyn FnOnce(&mut World, u64) + Send>),
+    /// Register an id reserved by `CommandBuffer::spawn`
+    Spawn(u64),
+    AddComponent(u64, EntityCommand),
+    RemoveComponent(u64, EntityCommand),
     DestroyEntity(u64),
+    DestroyRecursive(u64),
 }
 
 /// Buffer for deferred commands - allows "Unity-like" immediate operations
 /// while maintaining ECS thread-safety
 pub struct CommandBuffer {
-    commands: Vec<Command>,
+    /// Commands are only `Send`; the lock (only ever taken through `&mut self`, so never
+    /// contended) makes the buffer `Sync` so a Scene can share it between threads
+    commands: Mutex<Vec<Command>>,
 }
 
 impl CommandBuffer {
     pub fn new() -> Self {
         Self {
-            commands: Vec::new(),
+            commands: Mutex::new(Vec::new()),
         }
     }
 
+    /// Reserve an entity id right away and schedule creating the entity - later commands
+    /// in this buffer can target the returned id
+    ///
+    /// The id is reserved atomically from the world's allocator (`World::entity_allocator`),
+    /// so buffers on different threads never hand out the same one.
+    ///
+    /// ```
+    /// # use ecs_hybrid::*;
+    /// let mut world = World::new();
+    /// let allocator = world.entity_allocator();
+    /// let mut commands = CommandBuffer::new();
+    ///
+    /// let entity = commands.spawn(&allocator);
+    /// commands.add_component(entity, Health { current: 5.0, max: 10.0 });
+    /// assert!(world.get_component::<Health>(entity).is_none());
+    ///
+    /// commands.execute(&mut world);
+    /// assert_eq!(world.get_component::<Health>(entity).unwrap().current, 5.0);
+    /// assert_ne!(world.create_entity(), entity);
+    /// ```
+    pub fn spawn(&mut self, allocator: &EntityAllocator) -> u64 {
+        let entity = allocator.reserve();
+        self.commands.get_mut().push(Command::Spawn(entity));
+        entity
+    }
+
     /// Schedule entity creation - returns a "future" entity ID
     pub fn create_entity<F>(&mut self, setup: F)
     where
         F: FnOnce(&mut World) -> u64 + Send + 'static,
     {
-        self.commands.push(Command::CreateEntity(Box::new(setup)));
+        self.commands
+            .get_mut()
+            .push(Command::CreateEntity(Box::new(setup)));
+    }
+
+    /// Schedule instantiating a prefab - the prefab is copied, so it can be edited afterwards
+    pub fn instantiate_prefab(&mut self, prefab: &Prefab) {
+        let prefab = prefab.clone();
+        self.create_entity(move |world| world.instantiate_prefab(&prefab));
+    }
+
+    /// Schedule instantiating a prefab asset, keeping the instance linked to it
+    pub fn instantiate_linked(&mut self, prefab: &PrefabHandle) {
+        let prefab = prefab.clone();
+        self.create_entity(move |world| world.instantiate_linked(&prefab));
     }
 
     /// Schedule adding a component
     pub fn add_component<T: Send + Sync + 'static>(&mut self, entity: u64, component: T) {
-        self.commands.push(Command::AddComponent(
+        self.commands.get_mut().push(Command::AddComponent(
             entity,
             Box::new(move |world, entity| {
                 world.add_component(entity, component);
@@ -43,7 +93,7 @@ impl CommandBuffer {
 
     /// Schedule removing a component
     pub fn remove_component<T: 'static>(&mut self, entity: u64) {
-        self.commands.push(Command::RemoveComponent(
+        self.commands.get_mut().push(Command::RemoveComponent(
             entity,
             Box::new(|world, entity| {
                 world.remove_component::<T>(entity);
@@ -53,16 +103,26 @@ impl CommandBuffer {
 
     /// Schedule entity destruction
     pub fn destroy_entity(&mut self, entity: u64) {
-        self.commands.push(Command::DestroyEntity(entity));
+        self.commands.get_mut().push(Command::DestroyEntity(entity));
+    }
+
+    /// Schedule destroying an entity and all of its children
+    pub fn destroy_recursive(&mut self, entity: u64) {
+        self.commands
+            .get_mut()
+            .push(Command::DestroyRecursive(entity));
     }
 
     /// Execute all buffered commands - called at safe synchronization points
     pub fn execute(&mut self, world: &mut World) {
-        for command in self.commands.drain(..) {
+        for command in self.commands.get_mut().drain(..) {
             match command {
                 Command::CreateEntity(func) => {
                     func(world);
                 }
+                Command::Spawn(entity) => {
+                    world.register_entity(entity);
+                }
                 Command::AddComponent(entity, func) => {
                     func(world, entity);
                 }
@@ -72,12 +132,15 @@ impl CommandBuffer {
                 Command::DestroyEntity(entity) => {
                     world.destroy_entity(entity);
                 }
+                Command::DestroyRecursive(entity) => {
+                    world.destroy_recursive(entity);
+                }
             }
         }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.commands.is_empty()
+        self.commands.lock().is_empty()
     }
 }
 
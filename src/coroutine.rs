@@ -0,0 +1,160 @@
+/// Unity-like coroutines - resumable tasks tied to an Entity, advanced once per frame
+use crate::game_object::{Entity, Scene};
+use std::collections::HashSet;
+
+/// What a coroutine waits for before it is resumed again
+pub enum Yield {
+    /// Resume once this many seconds of frame time have passed
+    WaitSeconds(f32),
+    /// Resume after this many frames
+    WaitFrames(u32),
+    /// Resume on the first frame the predicate returns true
+    WaitUntil(Box<dyn FnMut(&Scene) -> bool + Send>),
+    /// Finished - the coroutine is dropped
+    Done,
+}
+
+/// A resumable task - each `resume` runs one step and says what to wait for next
+///
+/// Implemented for closures, so a state machine can be written inline:
+/// ```
+/// # use ecs_hybrid::*;
+/// # let scene = Scene::new();
+/// # let spawner = scene.instantiate();
+/// let mut spawned = false;
+/// scene.start_coroutine(&spawner, move |_entity: &Entity, scene: &Scene| {
+///     if spawned {
+///         scene.instantiate().add_component(Name::new("Enemy"));
+///         return Yield::Done;
+///     }
+///     spawned = true;
+///     Yield::WaitSeconds(2.0)
+/// });
+/// ```
+pub trait Coroutine: Send + 'static {
+    fn resume(&mut self, entity: &Entity, scene: &Scene) -> Yield;
+}
+
+impl<F> Coroutine for F
+where
+    F: FnMut(&Entity, &Scene) -> Yield + Send + 'static,
+{
+    fn resume(&mut self, entity: &Entity, scene: &Scene) -> Yield {
+        self(entity, scene)
+    }
+}
+
+/// Handle used to stop a running coroutine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CoroutineId(u64);
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+struct RunningCoroutine {
+    id: CoroutineId,
+    entity: u64,
+    wait: Yield,
+    routine: Box<dyn Coroutine>,
+}
+
+impl RunningCoroutine {
+    /// Advance the current wait by one frame - true when the coroutine should resume
+    fn ready(&mut self, scene: &Scene, delta_time: f32) -> bool {
+        match &mut self.wait {
+            Yield::WaitSeconds(remaining) => {
+                *remaining -= delta_time;
+                *remaining <= 0.0
+            }
+            Yield::WaitFrames(remaining) => {
+                *remaining = remaining.saturating_sub(1);
+                *remaining == 0
+            }
+            Yield::WaitUntil(predicate) => predicate(scene),
+            Yield::Done => true,
+        }
+    }
+}
+
+/// Coroutine scheduler owned by the Scene
+///
+/// New coroutines run their first step on the next `Scene::update_coroutines` (called by
+/// `SystemExecutor::run`, not `execute`).
+#[derive(Default)]
+pub(crate) struct CoroutineScheduler {
+    next_id: u64,
+    running: Vec<RunningCoroutine>,
+    stopped: HashSet<CoroutineId>,
+    stopped_entities: HashSet<u64>,
+}
+
+impl CoroutineScheduler {
+    pub(crate) fn start(&mut self, entity: u64, routine: Box<dyn Coroutine>) -> CoroutineId {
+        let id = CoroutineId(self.next_id);
+        self.next_id += 1;
+        self.running.push(RunningCoroutine {
+            id,
+            entity,
+            wait: Yield::WaitFrames(1),
+            routine,
+        });
+        id
+    }
+
+    pub(crate) fn stop(&mut self, id: CoroutineId) {
+        self.running.retain(|c| c.id != id);
+        // It may be mid-step right now - drop it when it is handed back
+        self.stopped.insert(id);
+    }
+
+    pub(crate) fn stop_all(&mut self, entity: u64) {
+        self.running.retain(|c| c.entity != entity);
+        self.stopped_entities.insert(entity);
+    }
+
+    /// Hand out every coroutine for one frame, leaving the scheduler unlocked while they run
+    fn take(&mut self) -> Vec<RunningCoroutine> {
+        self.stopped.clear();
+        self.stopped_entities.clear();
+        std::mem::take(&mut self.running)
+    }
+
+    /// Put back the coroutines that are still waiting, ahead of any started meanwhile
+    fn put_back(&mut self, mut survivors: Vec<RunningCoroutine>) {
+        survivors.retain(|c| {
+            !self.stopped.contains(&c.id) && !self.stopped_entities.contains(&c.entity)
+        });
+        survivors.append(&mut self.running);
+        self.running = survivors;
+        self.stopped.clear();
+        self.stopped_entities.clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.running.len()
+    }
+}
+
+/// Advance every coroutine of the scene by one frame
+pub(crate) fn update_coroutines(scene: &Scene, delta_time: f32) {
+    let running = scene.coroutines().lock().take();
+    let mut survivors = Vec::with_capacity(running.len());
+
+    for mut coroutine in running {
+        // Cancelled automatically once the entity is destroyed
+        if !scene.contains_entity(coroutine.entity) {
+            continue;
+        }
+        // Paused (not advanced) while the entity is inactive, like Unity
+        if !scene.is_entity_active(coroutine.entity) || !coroutine.ready(scene, delta_time) {
+            survivors.push(coroutine);
+            continue;
+        }
+        let entity = scene.get_entity(coroutine.entity);
+        coroutine.wait = coroutine.routine.resume(&entity, scene);
+        if !matches!(coroutine.wait, Yield::Done) {
+            survivors.push(coroutine);
+        }
+    }
+
+    scene.coroutines().lock().put_back(survivors);
+}
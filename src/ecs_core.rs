@@ -1,262 +1,1994 @@
 /// Core ECS implementation - the performance-critical parallel system
+use crate::behaviour::Script;
+use crate::bitset::BitSet;
+use crate::borrow_tracker::{BorrowTracker, BorrowTrackers};
+use crate::hierarchy::Hierarchy;
+use crate::layers::{EntityMasks, LayerMask};
+use crate::lookup::{LookupIndex, TagIndex};
+use crate::migration::LoadReport;
+use crate::patch::{self, WorldPatch};
+use crate::prefab::{self, Prefab, PrefabHandle, PrefabLink};
+use crate::reflect::{Reflect, ReflectError, Value};
+use crate::registry::{ComponentInfo, ComponentRegistry};
+use crate::scene_format::{self, SceneError};
+use crate::snapshot::{self, DecodedComponent, DecodedEntity, SnapshotError};
+use crate::state_hash::{self, StateHash};
+use crate::world_snapshot::{SnapshotColumn, WorldSnapshot};
+use crate::{Health, Name, Transform, Velocity};
+use parking_lot::lock_api::{ArcRwLockReadGuard, ArcRwLockWriteGuard};
+use parking_lot::{
+    MappedRwLockReadGuard, MappedRwLockWriteGuard, Mutex, RawRwLock, RwLock, RwLockReadGuard,
+    RwLockWriteGuard,
+};
 use std::any::{Any, TypeId};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 /// Component storage - type-erased for flexibility
 pub trait ComponentStorage: Send + Sync {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn remove(&mut self, entity: u64);
+    fn contains(&self, entity: u64) -> bool;
+    /// Visit every entity holding the component
+    fn for_each_entity(&self, f: &mut dyn FnMut(u64));
+    fn type_name(&self) -> &'static str;
+    /// Number of instances of the component on an entity
+    fn count(&self, entity: u64) -> usize;
+    /// One instance of the component on an entity, type-erased
+    fn get_dyn(&self, entity: u64, index: usize) -> Option<&dyn Any>;
+    fn get_dyn_mut(&mut self, entity: u64, index: usize) -> Option<&mut dyn Any>;
+    fn is_enabled(&self, entity: u64) -> bool;
+    fn set_enabled(&mut self, entity: u64, enabled: bool);
 }
 
 // ---------------------------------------------------------------------------------------------------------------------
 
 /// Concrete storage for a specific component type
+///
+/// Zero-sized components without drop glue (`struct Enemy;`) are detected automatically
+/// and stored as markers: one bit per entity id instead of a map entry. They are read back
+/// as references to the zero-sized value, so every API works the same for them - except
+/// that an entity holds a marker at most once.
+#[derive(Clone)]
 pub struct TypedStorage<T: 'static> {
     components: HashMap<u64, Vec<T>>, // Support multiple components per entity
+    markers: BitSet,                  // Entities holding a marker component
+    disabled: HashSet<u64>,           // Entities whose component of this type is disabled
 }
 
 impl<T: 'static> TypedStorage<T> {
+    /// Whether `T` is stored as a marker bitset
+    pub const IS_MARKER: bool = std::mem::size_of::<T>() == 0 && !std::mem::needs_drop::<T>();
+
     pub fn new() -> Self {
         Self {
             components: HashMap::new(),
+            markers: BitSet::default(),
+            disabled: HashSet::new(),
+        }
+    }
+
+    /// Reference to the marker value
+    fn marker<'a>() -> &'a mut T {
+        assert!(Self::IS_MARKER);
+        // SAFETY: T is zero-sized, so any aligned non-null pointer is valid for it, and this
+        // is only reached for entities whose bit was set by inserting a value (T is inhabited)
+        unsafe { NonNull::<T>::dangling().as_mut() }
+    }
+
+    pub fn insert(&mut self, entity: u64, component: T) {
+        if Self::IS_MARKER {
+            self.markers.insert(entity);
+            return;
+        }
+        self.components.entry(entity).or_default().push(component);
+    }
+
+    pub fn contains(&self, entity: u64) -> bool {
+        if Self::IS_MARKER {
+            return self.markers.contains(entity);
+        }
+        self.components.contains_key(&entity)
+    }
+
+    pub fn get(&self, entity: u64) -> Option<&T> {
+        if Self::IS_MARKER {
+            return self.markers.contains(entity).then(|| &*Self::marker());
+        }
+        self.components.get(&entity).and_then(|v| v.first())
+    }
+
+    pub fn get_mut(&mut self, entity: u64) -> Option<&mut T> {
+        if Self::IS_MARKER {
+            return self.markers.contains(entity).then(Self::marker);
+        }
+        self.components.get_mut(&entity).and_then(|v| v.first_mut())
+    }
+
+    /// Every instance of the component on an entity, without cloning
+    pub fn get_slice(&self, entity: u64) -> Option<&[T]> {
+        if Self::IS_MARKER {
+            return self
+                .markers
+                .contains(entity)
+                .then(|| std::slice::from_ref(&*Self::marker()));
+        }
+        self.components.get(&entity).map(Vec::as_slice)
+    }
+
+    pub fn get_all(&self, entity: u64) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.get_slice(entity)
+            .map(<[T]>::to_vec)
+            .unwrap_or_default()
+    }
+
+    /// Remove every instance of this component from an entity
+    pub fn remove(&mut self, entity: u64) {
+        self.components.remove(&entity);
+        self.markers.remove(entity);
+        self.disabled.remove(&entity);
+    }
+
+    /// Number of entities holding the component
+    pub fn len(&self) -> usize {
+        self.components.len() + self.markers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Enable or disable the component on an entity - disabled components are skipped
+    /// by queries unless `QueryFilter::include_disabled` is set
+    pub fn set_enabled(&mut self, entity: u64, enabled: bool) {
+        if enabled {
+            self.disabled.remove(&entity);
+        } else if self.contains(entity) {
+            self.disabled.insert(entity);
+        }
+    }
+
+    pub fn is_enabled(&self, entity: u64) -> bool {
+        !self.disabled.contains(&entity)
+    }
+
+    /// Whether the component of this entity passes the filter's enabled check
+    fn visible(&self, entity: u64, filter: &QueryFilter) -> bool {
+        filter.include_disabled || self.disabled.is_empty() || !self.disabled.contains(&entity)
+    }
+
+    // Iterators chain both representations - one of the two is always empty
+
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &T)> {
+        self.components
+            .iter()
+            .filter_map(|(e, v)| v.first().map(|c| (*e, c)))
+            .chain(self.markers.iter().map(|e| (e, &*Self::marker())))
+    }
+
+    /// Iterate over every instance of the component, grouped per entity
+    pub fn iter_all(&self) -> impl Iterator<Item = (u64, &[T])> {
+        self.components
+            .iter()
+            .map(|(e, v)| (*e, v.as_slice()))
+            .chain(
+                self.markers
+                    .iter()
+                    .map(|e| (e, std::slice::from_ref(&*Self::marker()))),
+            )
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (u64, &mut T)> {
+        self.components
+            .iter_mut()
+            .filter_map(|(e, v)| v.first_mut().map(|c| (*e, c)))
+            .chain(self.markers.iter().map(|e| (e, Self::marker())))
+    }
+
+    /// `iter`, sorted by entity id when `sorted` - in `HashMap` order otherwise
+    fn iter_sorted(&self, sorted: bool) -> impl Iterator<Item = (u64, &T)> {
+        let mut ordered = Vec::new();
+        if sorted {
+            ordered.extend(self.iter());
+            ordered.sort_unstable_by_key(|(e, _)| *e);
+        }
+        ordered
+            .into_iter()
+            .chain((!sorted).then(|| self.iter()).into_iter().flatten())
+    }
+
+    /// `iter_mut` skipping disabled components unless the filter includes them, sorted by
+    /// entity id when `sorted`
+    fn iter_mut_visible(
+        &mut self,
+        filter: &QueryFilter,
+        sorted: bool,
+    ) -> impl Iterator<Item = (u64, &mut T)> {
+        let include_disabled = filter.include_disabled;
+        let disabled = &self.disabled;
+        let mut iter = Some(
+            self.components
+                .iter_mut()
+                .filter_map(|(e, v)| v.first_mut().map(|c| (*e, c)))
+                .chain(self.markers.iter().map(|e| (e, Self::marker())))
+                .filter(move |(e, _)| include_disabled || !disabled.contains(e)),
+        );
+        let mut ordered = Vec::new();
+        if sorted {
+            ordered.extend(iter.take().unwrap());
+            ordered.sort_unstable_by_key(|(e, _)| *e);
+        }
+        ordered.into_iter().chain(iter.into_iter().flatten())
+    }
+
+    /// Get multiple mutable references from different entities
+    ///
+    /// # Safety
+    /// Caller must ensure entity IDs are unique
+    pub unsafe fn get_many_mut<const N: usize>(
+        &mut self,
+        entities: [u64; N],
+    ) -> [Option<&mut T>; N] {
+        let mut results: [Option<&mut T>; N] = std::array::from_fn(|_| None);
+        let storage = self as *mut Self;
+
+        for (i, &entity) in entities.iter().enumerate() {
+            // SAFETY: We're transmuting the lifetime to 'static temporarily
+            // This is safe because we know the caller ensures unique entity IDs
+            results[i] = (*storage).get_mut(entity);
+        }
+
+        results
+    }
+}
+
+impl<T: 'static> Default for TypedStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + Sync + 'static> ComponentStorage for TypedStorage<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn remove(&mut self, entity: u64) {
+        TypedStorage::remove(self, entity);
+    }
+
+    fn contains(&self, entity: u64) -> bool {
+        TypedStorage::contains(self, entity)
+    }
+
+    fn for_each_entity(&self, f: &mut dyn FnMut(u64)) {
+        self.components.keys().for_each(|e| f(*e));
+        self.markers.iter().for_each(f);
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn count(&self, entity: u64) -> usize {
+        self.get_slice(entity).map_or(0, <[T]>::len)
+    }
+
+    fn get_dyn(&self, entity: u64, index: usize) -> Option<&dyn Any> {
+        self.get_slice(entity)?
+            .get(index)
+            .map(|component| component as &dyn Any)
+    }
+
+    fn get_dyn_mut(&mut self, entity: u64, index: usize) -> Option<&mut dyn Any> {
+        if Self::IS_MARKER {
+            return (index == 0 && self.markers.contains(entity))
+                .then(|| Self::marker() as &mut dyn Any);
+        }
+        self.components
+            .get_mut(&entity)?
+            .get_mut(index)
+            .map(|component| component as &mut dyn Any)
+    }
+
+    fn is_enabled(&self, entity: u64) -> bool {
+        TypedStorage::is_enabled(self, entity)
+    }
+
+    fn set_enabled(&mut self, entity: u64, enabled: bool) {
+        TypedStorage::set_enabled(self, entity, enabled);
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Which entities and components a query sees
+///
+/// By default queries skip entities that are inactive in the hierarchy and components
+/// that are disabled; opt in to see them. Entities can also be narrowed down by layer and
+/// by the presence of other components - typically markers like `struct Enemy;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryFilter {
+    pub include_inactive: bool,
+    pub include_disabled: bool,
+    /// Only entities on at least one of these layers
+    pub layers: LayerMask,
+    with: Vec<TypeId>,
+    without: Vec<TypeId>,
+}
+
+impl Default for QueryFilter {
+    fn default() -> Self {
+        Self {
+            include_inactive: false,
+            include_disabled: false,
+            layers: LayerMask::ALL,
+            with: Vec::new(),
+            without: Vec::new(),
+        }
+    }
+}
+
+impl QueryFilter {
+    /// See everything, including inactive entities and disabled components
+    pub const ALL: Self = Self {
+        include_inactive: true,
+        include_disabled: true,
+        layers: LayerMask::ALL,
+        with: Vec::new(),
+        without: Vec::new(),
+    };
+
+    pub fn include_inactive(mut self) -> Self {
+        self.include_inactive = true;
+        self
+    }
+
+    pub fn include_disabled(mut self) -> Self {
+        self.include_disabled = true;
+        self
+    }
+
+    /// Only see entities on at least one layer of the mask
+    pub fn in_layers(mut self, layers: LayerMask) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Only see entities that also have a `T`
+    pub fn with<T: 'static>(mut self) -> Self {
+        Self::push(&mut self.with, TypeId::of::<T>());
+        self
+    }
+
+    /// Skip entities that have a `T`
+    pub fn without<T: 'static>(mut self) -> Self {
+        Self::push(&mut self.without, TypeId::of::<T>());
+        self
+    }
+
+    fn push(types: &mut Vec<TypeId>, type_id: TypeId) {
+        if !types.contains(&type_id) {
+            types.push(type_id);
+        }
+    }
+}
+
+/// A QueryFilter resolved against a World
+///
+/// `with`/`without` columns are read-locked for as long as the query lives, like the queried
+/// columns, and checked in place - a bit test for markers - instead of being copied.
+struct EntityFilter<'w> {
+    filter: QueryFilter,
+    hierarchy: &'w Hierarchy,
+    masks: &'w EntityMasks,
+    with: Vec<ArcColumnReadGuard>,
+    without: Vec<ArcColumnReadGuard>,
+    /// Nothing matches: a `with` column does not exist or a `without` type is queried
+    excluded: bool,
+}
+
+impl<'w> EntityFilter<'w> {
+    /// `queried` are the component types the query itself locks mutably - their columns
+    /// cannot be locked again, and the query already requires them
+    fn new(
+        storages: &HashMap<TypeId, Column>,
+        hierarchy: &'w Hierarchy,
+        masks: &'w EntityMasks,
+        filter: QueryFilter,
+        queried: &[TypeId],
+    ) -> Self {
+        // Locked in TypeId order, see `World`
+        let lock = |types: &[TypeId]| {
+            let mut types: Vec<TypeId> = types
+                .iter()
+                .filter(|type_id| !queried.contains(type_id))
+                .copied()
+                .collect();
+            types.sort_unstable();
+            types
+                .iter()
+                .filter_map(|type_id| storages.get(type_id).map(Column::read_arc))
+                .collect::<Vec<_>>()
+        };
+        // A `with` type nobody holds yet matches no entity
+        let missing = filter
+            .with
+            .iter()
+            .any(|type_id| !queried.contains(type_id) && !storages.contains_key(type_id));
+
+        Self {
+            with: lock(&filter.with),
+            without: lock(&filter.without),
+            excluded: missing
+                || filter
+                    .without
+                    .iter()
+                    .any(|type_id| queried.contains(type_id)),
+            filter,
+            hierarchy,
+            masks,
+        }
+    }
+
+    /// Entity-level part of the filter
+    fn matches(&self, entity: u64) -> bool {
+        let filter = &self.filter;
+        (filter.layers == LayerMask::ALL || self.masks.layers(entity).intersects(filter.layers))
+            && (filter.include_inactive
+                || self.hierarchy.all_active()
+                || self.hierarchy.is_active(entity))
+            && !self.excluded
+            && self.with.iter().all(|column| column.contains(entity))
+            && !self.without.iter().any(|column| column.contains(entity))
+    }
+
+    /// Entity-level part plus the enabled check of one component
+    fn matches_component<T>(&self, storage: &TypedStorage<T>, entity: u64) -> bool {
+        storage.visible(entity, &self.filter) && self.matches(entity)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Source of `Column` ids - unique across every World
+static NEXT_COLUMN_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Per-component-type column, locked independently of the rest of the world
+///
+/// Write accesses are counted so `World::snapshot` can share the column with the previous
+/// snapshot instead of copying it again when it was not written in between.
+pub(crate) struct Column {
+    /// Shared so `Entity` guards can own their lock (`read_arc`/`write_arc`). Those guards
+    /// also hold the world read lock, so whoever has `&mut World` holds the only reference.
+    storage: Arc<RwLock<Box<dyn ComponentStorage>>>,
+    id: u64,
+    writes: AtomicU64,
+    /// Copy made by the last snapshot, with the version it was taken at
+    snapshot: Mutex<Option<(ColumnVersion, Arc<dyn ComponentStorage>)>>,
+}
+
+/// Owning read lock on a column (see `Column::read_arc`)
+pub(crate) type ArcColumnReadGuard = ArcRwLockReadGuard<RawRwLock, Box<dyn ComponentStorage>>;
+/// Owning write lock on a column (see `Column::write_arc`)
+pub(crate) type ArcColumnWriteGuard = ArcRwLockWriteGuard<RawRwLock, Box<dyn ComponentStorage>>;
+
+/// Column id + write count - equal versions mean equal contents
+pub(crate) type ColumnVersion = (u64, u64);
+
+impl Column {
+    pub(crate) fn new(storage: Box<dyn ComponentStorage>) -> Self {
+        Self {
+            storage: Arc::new(RwLock::new(storage)),
+            id: NEXT_COLUMN_ID.fetch_add(1, Ordering::Relaxed),
+            writes: AtomicU64::new(0),
+            snapshot: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn read_recursive(&self) -> RwLockReadGuard<'_, Box<dyn ComponentStorage>> {
+        self.storage.read_recursive()
+    }
+
+    pub(crate) fn write(&self) -> RwLockWriteGuard<'_, Box<dyn ComponentStorage>> {
+        let guard = self.storage.write();
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        guard
+    }
+
+    /// Read lock that owns its column, so it does not borrow the World
+    pub(crate) fn read_arc(&self) -> ArcColumnReadGuard {
+        self.storage.read_arc_recursive()
+    }
+
+    /// Write lock that owns its column, so it does not borrow the World
+    pub(crate) fn write_arc(&self) -> ArcColumnWriteGuard {
+        let guard = self.storage.write_arc();
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        guard
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut Box<dyn ComponentStorage> {
+        *self.writes.get_mut() += 1;
+        self.exclusive()
+    }
+
+    /// Exclusive read access - not counted as a write
+    fn get(&mut self) -> &(dyn ComponentStorage + 'static) {
+        &**self.exclusive()
+    }
+
+    fn exclusive(&mut self) -> &mut Box<dyn ComponentStorage> {
+        Arc::get_mut(&mut self.storage)
+            .expect("column guard outlived the World lock it was taken under")
+            .get_mut()
+    }
+
+    /// Remove an entity's components - only counted as a write if it had any
+    fn remove(&mut self, entity: u64) {
+        if self.get().contains(entity) {
+            self.get_mut().remove(entity);
+        }
+    }
+
+    fn version(&self) -> ColumnVersion {
+        (self.id, self.writes.load(Ordering::Relaxed))
+    }
+}
+
+/// Read lock on a single component instance
+pub type ComponentReadGuard<'a, T> = MappedRwLockReadGuard<'a, T>;
+/// Write lock on a single component instance
+pub type ComponentWriteGuard<'a, T> = MappedRwLockWriteGuard<'a, T>;
+/// Read lock on the whole column of a component type
+pub type StorageReadGuard<'a, T> = MappedRwLockReadGuard<'a, TypedStorage<T>>;
+/// Write lock on the whole column of a component type
+pub type StorageWriteGuard<'a, T> = MappedRwLockWriteGuard<'a, TypedStorage<T>>;
+
+/// Source of entity ids, shared between a World and the command buffers spawning into it
+///
+/// Reserving only needs `&self`, so ids can be handed out from any thread while the World
+/// itself is borrowed elsewhere; a reserved id becomes a live entity once it is registered
+/// with `World::register_entity`.
+#[derive(Debug, Default)]
+pub struct EntityAllocator {
+    next: AtomicU64,
+}
+
+impl EntityAllocator {
+    /// Reserve a fresh entity id - never handed out again by this allocator
+    pub fn reserve(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub(crate) fn peek(&self) -> u64 {
+        self.next.load(Ordering::Relaxed)
+    }
+
+    /// Move the allocator forward to `next` - never back, so ids already handed out (by a
+    /// `CommandBuffer` whose commands are not applied yet, for instance) are never reused
+    pub(crate) fn advance_to(&self, next: u64) {
+        self.next.fetch_max(next, Ordering::Relaxed);
+    }
+}
+
+/// Why `World::clone_entity` could not copy an entity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloneError {
+    /// A component type was not registered with `World::register_clone`
+    NotCloneable {
+        entity: u64,
+        component: &'static str,
+    },
+    /// The entity does not exist (or was destroyed)
+    MissingEntity(u64),
+}
+
+impl std::fmt::Display for CloneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CloneError::NotCloneable { entity, component } => write!(
+                f,
+                "component {} of entity {} is not cloneable - register it with World::register_clone",
+                component, entity
+            ),
+            CloneError::MissingEntity(entity) => {
+                write!(f, "cannot clone entity {}: it does not exist", entity)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CloneError {}
+
+/// The core ECS world - thread-safe and parallel-friendly
+///
+/// Every component type lives in its own `Column` with its own lock. Through `&World`,
+/// readers and writers only contend when they touch the same component type; structural
+/// changes (creating/destroying entities, registering a new component type) need `&mut World`.
+///
+/// Lock order: a thread holding a write guard of one component type and then locking
+/// another can deadlock against a thread doing the reverse, which the old single world lock
+/// never could. When holding several column guards at once, take them in `TypeId` order (as
+/// `Entity::with` does); read guards alone never deadlock.
+pub struct World {
+    allocator: Arc<EntityAllocator>,
+    storages: HashMap<TypeId, Column>,
+    entities: Vec<u64>,
+    /// Same ids as `entities`, for O(1) `contains_entity`
+    entity_set: HashSet<u64>,
+    borrow_trackers: Arc<BorrowTrackers>,
+    // Shared with snapshots, copied on the first write after one is taken (`Arc::make_mut`)
+    hierarchy: Arc<Hierarchy>,
+    masks: Arc<EntityMasks>,
+    tags: Arc<TagIndex>,
+    lookup: RwLock<Arc<LookupIndex>>,
+    prefab_links: Arc<HashMap<u64, PrefabLink>>,
+    registry: ComponentRegistry,
+    /// Queries and `entities()` iterate in entity id order
+    deterministic: bool,
+    /// Set by `Scene` - destroying an entity with scripts waits for their `on_destroy`
+    defer_scripted_destroy: bool,
+    /// Entities whose destruction waits for `on_destroy` (see `take_pending_destroy`)
+    pending_destroy: Vec<u64>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        let mut world = Self {
+            allocator: Arc::new(EntityAllocator::default()),
+            storages: HashMap::new(),
+            entities: Vec::new(),
+            entity_set: HashSet::new(),
+            borrow_trackers: Arc::new(BorrowTrackers::default()),
+            hierarchy: Arc::default(),
+            masks: Arc::default(),
+            tags: Arc::default(),
+            lookup: RwLock::default(),
+            prefab_links: Arc::default(),
+            registry: ComponentRegistry::new(),
+            deterministic: false,
+            defer_scripted_destroy: false,
+            pending_destroy: Vec::new(),
+        };
+        world.register_builtin::<Transform>("Transform");
+        world.register_builtin::<Velocity>("Velocity");
+        world.register_builtin::<Health>("Health");
+        world.register_builtin::<Name>("Name");
+        world
+    }
+
+    fn register_builtin<T: Clone + Default + std::fmt::Debug + Reflect>(&mut self, id: &str) {
+        self.registry.set_id::<T>(id);
+        self.registry.register_clone::<T>();
+        self.registry.register_debug::<T>();
+        self.registry.register_serializable::<T>();
+    }
+
+    pub fn create_entity(&mut self) -> u64 {
+        let entity = self.allocator.reserve();
+        self.push_entity(entity);
+        entity
+    }
+
+    /// Add to the entity list, keeping it in id order in deterministic mode
+    fn push_entity(&mut self, entity: u64) {
+        self.entity_set.insert(entity);
+        match self.entities.last() {
+            Some(&last) if self.deterministic && entity < last => {
+                let index = self.entities.partition_point(|e| *e < entity);
+                self.entities.insert(index, entity);
+            }
+            _ => self.entities.push(entity),
+        }
+    }
+
+    /// Make every query and `entities()` iterate in entity id order, so systems visit
+    /// entities in the same order on every run - for replays and lockstep simulations
+    ///
+    /// By default queries iterate their column in `HashMap` order, which changes from run
+    /// to run. Ids are allocated in increasing order, so id order is also creation order.
+    /// Deterministic queries sort the entities they visit, which costs a little time.
+    ///
+    /// ```
+    /// # use ecs_hybrid::*;
+    /// let mut world = World::new();
+    /// world.set_deterministic(true);
+    /// for i in 0..100 {
+    ///     let entity = world.create_entity();
+    ///     world.add_component(entity, Transform::new(i as f32, 0.0, 0.0));
+    /// }
+    /// let order: Vec<u64> = world.query::<Transform>().unwrap().iter().map(|(e, _)| e).collect();
+    /// assert_eq!(order, (0..100).collect::<Vec<_>>());
+    /// ```
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+        if deterministic {
+            self.entities.sort_unstable();
+        }
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Create the entities of a prefab - returns the root entity
+    pub fn instantiate_prefab(&mut self, prefab: &Prefab) -> u64 {
+        prefab::instantiate(self, prefab)
+    }
+
+    /// Create the entities of a prefab asset and keep them linked to it, so they follow
+    /// edits made with `edit_prefab` - returns the root entity
+    pub fn instantiate_linked(&mut self, prefab: &PrefabHandle) -> u64 {
+        prefab::instantiate_linked(self, prefab)
+    }
+
+    /// Edit a prefab asset - linked instances get the new values of every component they
+    /// have not overridden
+    pub fn edit_prefab(&mut self, prefab: &PrefabHandle, edit: impl FnOnce(&mut Prefab)) {
+        prefab::edit(self, prefab, edit);
+    }
+
+    /// Prefab asset an entity was instantiated from
+    pub fn prefab_of(&self, entity: u64) -> Option<PrefabHandle> {
+        self.prefab_links
+            .get(&entity)
+            .map(|link| link.prefab().clone())
+    }
+
+    /// Type names of the components of a linked entity that differ from its prefab - changed,
+    /// removed, or added to the instance
+    pub fn prefab_overrides(&self, entity: u64) -> Vec<&'static str> {
+        self.prefab_links
+            .get(&entity)
+            .map(|link| prefab::overrides(self, link, entity))
+            .unwrap_or_default()
+    }
+
+    /// Reset the prefab components of a linked entity to the prefab's values and remove the
+    /// components added to it (enabled flags are kept)
+    pub fn revert_to_prefab(&mut self, entity: u64) {
+        if let Some(link) = self.prefab_links.get(&entity).cloned() {
+            prefab::revert(self, &link, entity);
+        }
+    }
+
+    /// Write the overrides of a linked entity into its prefab (the variant, for variant
+    /// instances) and refresh the other instances - returns the type names applied
+    pub fn apply_overrides_to_prefab(&mut self, entity: u64) -> Vec<&'static str> {
+        match self.prefab_links.get(&entity).cloned() {
+            Some(link) => prefab::apply_overrides(self, &link, entity),
+            None => Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_prefab_link(&mut self, entity: u64, link: PrefabLink) {
+        Arc::make_mut(&mut self.prefab_links).insert(entity, link);
+    }
+
+    pub(crate) fn prefab_links(&self) -> impl Iterator<Item = (u64, &PrefabLink)> {
+        self.prefab_links
+            .iter()
+            .map(|(entity, link)| (*entity, link))
+    }
+
+    /// Create a new entity ID without registering it yet
+    pub fn create_entity_id(&mut self) -> u64 {
+        self.allocator.reserve()
+    }
+
+    /// Reserve an entity ID through `&self` - register it later with `register_entity`
+    pub fn reserve_entity(&self) -> u64 {
+        self.allocator.reserve()
+    }
+
+    /// The id allocator, for reserving ids without access to the World (see `CommandBuffer::spawn`)
+    pub fn entity_allocator(&self) -> Arc<EntityAllocator> {
+        self.allocator.clone()
+    }
+
+    /// Register an entity in the world
+    pub fn register_entity(&mut self, entity: u64) {
+        if !self.entity_set.contains(&entity) {
+            self.push_entity(entity);
+        }
+    }
+
+    /// Exclusive access to the typed storage of `T` (no locking needed with `&mut World`)
+    fn typed_storage_mut<T: 'static>(&mut self) -> Option<&mut TypedStorage<T>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<TypedStorage<T>>()
+    }
+
+    pub fn add_component<T: Send + Sync + 'static>(&mut self, entity: u64, component: T) {
+        if LookupIndex::is_indexed::<T>() {
+            return self.update_names(|world| {
+                Arc::make_mut(world.lookup.get_mut()).insert(entity, &component);
+                world.insert_component(entity, component);
+            });
+        }
+        self.insert_component(entity, component);
+    }
+
+    /// `add_component` without touching the Name index
+    fn insert_component<T: Send + Sync + 'static>(&mut self, entity: u64, component: T) {
+        let type_id = TypeId::of::<T>();
+
+        let storage = self.storages.entry(type_id).or_insert_with(|| {
+            self.registry.register::<T>();
+            Column::new(Box::new(TypedStorage::<T>::new()))
+        });
+
+        storage
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<TypedStorage<T>>()
+            .unwrap()
+            .insert(entity, component);
+    }
+
+    /// Make components of type `T` copyable by `clone_entity` and `snapshot`
+    ///
+    /// The built-in components and every component added through a prefab are registered
+    /// automatically.
+    pub fn register_clone<T: Clone + Send + Sync + 'static>(&mut self) {
+        self.registry.register_clone::<T>();
+    }
+
+    /// Metadata of every component type this World knows
+    pub fn registry(&self) -> &ComponentRegistry {
+        &self.registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut ComponentRegistry {
+        &mut self.registry
+    }
+
+    /// Create a copy of an entity with every component (all instances of multi-instance
+    /// components, and their enabled state), its layers, tags, active state and parent -
+    /// children are not copied. Returns the new entity
+    ///
+    /// Fails, before creating anything, if the entity does not exist or one of its components
+    /// was not registered with `register_clone` (the built-in components and prefab
+    /// components are).
+    pub fn clone_entity(&mut self, src: u64) -> Result<u64, CloneError> {
+        self.update_names(|world| world.clone_entity_indexed(src))
+    }
+
+    /// `clone_entity`, indexing the copied Names itself
+    fn clone_entity_indexed(&mut self, src: u64) -> Result<u64, CloneError> {
+        if !self.contains_entity(src) {
+            return Err(CloneError::MissingEntity(src));
+        }
+        let mut clones = Vec::new();
+        for (type_id, column) in &mut self.storages {
+            let storage = column.get();
+            if !storage.contains(src) {
+                continue;
+            }
+            let clone = self
+                .registry
+                .get_by_type_id(*type_id)
+                .and_then(|info| info.clone_components_fn());
+            match clone {
+                Some(clone) => clones.push((*type_id, clone)),
+                None => {
+                    return Err(CloneError::NotCloneable {
+                        entity: src,
+                        component: storage.type_name(),
+                    })
+                }
+            }
+        }
+
+        let dst = self.create_entity();
+        for (type_id, clone) in clones {
+            clone(
+                self.storages.get_mut(&type_id).unwrap().get_mut().as_mut(),
+                src,
+                dst,
+            );
+        }
+        if let Some(names) = self.storages.get_mut(&TypeId::of::<Name>()) {
+            let names = names
+                .get()
+                .as_any()
+                .downcast_ref::<TypedStorage<Name>>()
+                .unwrap();
+            for name in names.get_slice(dst).unwrap_or_default() {
+                Arc::make_mut(self.lookup.get_mut()).insert(dst, name);
+            }
+        }
+        Arc::make_mut(&mut self.masks).copy(src, dst);
+        Arc::make_mut(&mut self.tags).copy(src, dst);
+        let hierarchy = Arc::make_mut(&mut self.hierarchy);
+        hierarchy.set_parent(dst, hierarchy.parent(src));
+        hierarchy.set_active(dst, hierarchy.is_active_self(src));
+        Ok(dst)
+    }
+
+    /// Component types present on an entity, in registration order, with their instance counts
+    pub fn components_of(&self, entity: u64) -> Vec<(&ComponentInfo, usize)> {
+        self.registry
+            .iter()
+            .filter_map(|info| {
+                let count = self
+                    .storages
+                    .get(&info.type_id())?
+                    .read_recursive()
+                    .count(entity);
+                (count > 0).then_some((info, count))
+            })
+            .collect()
+    }
+
+    /// Readable multi-line description of an entity and its components
+    ///
+    /// Component values are printed with `Debug` when their type was registered with
+    /// `ComponentRegistry::register_debug` (the built-in components are).
+    ///
+    /// ```
+    /// # use ecs_hybrid::*;
+    /// let mut world = World::new();
+    /// let entity = world.create_entity();
+    /// world.add_component(entity, Name::new("Player"));
+    /// world.add_component(entity, Health::new(100.0));
+    /// println!("{}", world.inspect(entity));
+    /// // Entity 0 "Player" (active)
+    /// //   Health: Health { current: 100.0, max: 100.0 }
+    /// //   Name: Name { value: "Player" }
+    /// ```
+    pub fn inspect(&self, entity: u64) -> String {
+        use std::fmt::Write;
+
+        if !self.contains_entity(entity) {
+            return format!("Entity {} (destroyed)", entity);
+        }
+        let mut out = format!("Entity {}", entity);
+        if let Some(name) = self.get_component::<Name>(entity) {
+            let _ = write!(out, " \"{}\"", name.value);
+        }
+        let mut details = vec![if self.is_active(entity) {
+            "active".to_string()
+        } else {
+            "inactive".to_string()
+        }];
+        if let Some(parent) = self.parent(entity) {
+            details.push(format!("parent {}", parent));
+        }
+        if !self.children(entity).is_empty() {
+            details.push(format!("{} children", self.children(entity).len()));
+        }
+        let layers = self.layers(entity);
+        if layers != LayerMask::DEFAULT {
+            details.push(format!("layers {:#x}", layers.0));
+        }
+        let tags = self.tags.of(entity);
+        if !tags.is_empty() {
+            details.push(format!("tags [{}]", tags.join(", ")));
+        }
+        let _ = write!(out, " ({})", details.join(", "));
+
+        for (info, count) in self.components_of(entity) {
+            let storage = self.storages[&info.type_id()].read_recursive();
+            let disabled = if storage.is_enabled(entity) {
+                ""
+            } else {
+                " (disabled)"
+            };
+            if count > 1 {
+                let _ = write!(out, "\n  {} x{}{}:", info.id(), count, disabled);
+            } else {
+                let _ = write!(out, "\n  {}{}:", info.id(), disabled);
+            }
+            for index in 0..count {
+                let value = storage
+                    .get_dyn(entity, index)
+                    .and_then(|value| info.format(value))
+                    .unwrap_or_else(|| format!("<{} bytes, no Debug registered>", info.size()));
+                let indent = if count > 1 { "\n    " } else { " " };
+                let _ = write!(out, "{}{}", indent, value);
+            }
+        }
+        out
+    }
+
+    /// Run `f` on a component seen through `Reflect`
+    ///
+    /// `component` is the registered ID, optionally with an instance index for
+    /// multi-instance components (`"BoxCollider[1]"`) - the first instance otherwise.
+    pub fn with_reflect<R>(
+        &self,
+        entity: u64,
+        component: &str,
+        f: impl FnOnce(&dyn Reflect) -> R,
+    ) -> Result<R, ReflectError> {
+        let (info, index) = self.reflected_component(component)?;
+        let storage = self.storages.get(&info.type_id());
+        let storage = storage.map(|column| column.read_recursive());
+        let value = storage
+            .as_ref()
+            .and_then(|storage| storage.get_dyn(entity, index))
+            .ok_or_else(|| ReflectError::MissingComponent {
+                entity,
+                component: component.to_string(),
+            })?;
+        Ok(f(info.reflect(value).unwrap()))
+    }
+
+    /// Like `with_reflect`, with write access to the component
+    pub fn with_reflect_mut<R>(
+        &self,
+        entity: u64,
+        component: &str,
+        f: impl FnOnce(&mut dyn Reflect) -> R,
+    ) -> Result<R, ReflectError> {
+        let (info, index) = self.reflected_component(component)?;
+        let missing = || ReflectError::MissingComponent {
+            entity,
+            component: component.to_string(),
+        };
+        let column = self.storages.get(&info.type_id()).ok_or_else(missing)?;
+        let mut storage = column.write();
+        // Renaming through reflection keeps the Name index up to date
+        let indexed = info.type_id() == TypeId::of::<Name>();
+        if indexed {
+            if let Some(names) = storage.as_any().downcast_ref::<TypedStorage<Name>>() {
+                Arc::make_mut(&mut self.lookup.write())
+                    .remove(entity, names.get_slice(entity).unwrap_or_default());
+            }
+        }
+        let result = storage
+            .get_dyn_mut(entity, index)
+            .map(|value| f(info.reflect_mut(value).unwrap()));
+        if indexed {
+            if let Some(names) = storage.as_any().downcast_ref::<TypedStorage<Name>>() {
+                let mut guard = self.lookup.write();
+                let lookup = Arc::make_mut(&mut guard);
+                for name in names.get_slice(entity).unwrap_or_default() {
+                    lookup.insert(entity, name);
+                }
+                lookup.advance(self.name_version());
+            }
+        }
+        result.ok_or_else(missing)
+    }
+
+    /// Read a component field by path, e.g. `"Health.current"` or `"BoxCollider[1].size.x"`
+    pub fn get_field(&self, entity: u64, path: &str) -> Result<Value, ReflectError> {
+        let (component, field) = path.split_once('.').unwrap_or((path, ""));
+        self.with_reflect(entity, component, |value| value.get(field))
+            .and_then(|value| value)
+            .map_err(|error| Self::full_path(error, component))
+    }
+
+    /// Write a component field by path, e.g. `world.set_field(player, "Health.current", 50.0)`
+    pub fn set_field(
+        &self,
+        entity: u64,
+        path: &str,
+        value: impl Into<Value>,
+    ) -> Result<(), ReflectError> {
+        let (component, field) = path.split_once('.').unwrap_or((path, ""));
+        self.with_reflect_mut(entity, component, |target| target.set(field, value.into()))
+            .and_then(|result| result)
+            .map_err(|error| Self::full_path(error, component))
+    }
+
+    /// Field errors only know the path inside the component - prefix the component
+    fn full_path(error: ReflectError, component: &str) -> ReflectError {
+        let join = |path: String| {
+            if path.is_empty() {
+                component.to_string()
+            } else {
+                format!("{}.{}", component, path)
+            }
+        };
+        match error {
+            ReflectError::UnknownField(path) => ReflectError::UnknownField(join(path)),
+            ReflectError::NotAValue(path) => ReflectError::NotAValue(join(path)),
+            ReflectError::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => ReflectError::TypeMismatch {
+                path: join(path),
+                expected,
+                found,
+            },
+            error => error,
+        }
+    }
+
+    /// Registered reflectable component and instance index of `"Id"` / `"Id[index]"`
+    fn reflected_component(
+        &self,
+        component: &str,
+    ) -> Result<(&ComponentInfo, usize), ReflectError> {
+        let (id, index) = match component.strip_suffix(']').and_then(|c| c.split_once('[')) {
+            Some((id, index)) => (
+                id,
+                index
+                    .parse()
+                    .map_err(|_| ReflectError::UnknownComponent(component.to_string()))?,
+            ),
+            None => (component, 0),
+        };
+        let info = self
+            .registry
+            .get_by_id(id)
+            .ok_or_else(|| ReflectError::UnknownComponent(id.to_string()))?;
+        if !info.is_reflected() {
+            return Err(ReflectError::NotReflected(id.to_string()));
+        }
+        Ok((info, index))
+    }
+
+    /// Save every entity with its components (except `Script`s) and hierarchy as text - see
+    /// SCENE_FORMAT.md
+    ///
+    /// ```
+    /// # use ecs_hybrid::*;
+    /// let mut world = World::new();
+    /// let player = world.create_entity();
+    /// world.add_component(player, Name::new("Player"));
+    /// world.add_component(player, Health::new(100.0));
+    /// let text = world.save_to_string().unwrap();
+    ///
+    /// let mut loaded = World::new();
+    /// let report = loaded.load_from_str(&text).unwrap();
+    /// assert_eq!(loaded.find_by_name("Player"), Some(report.entities.map(player)));
+    /// ```
+    pub fn save_to_string(&self) -> Result<String, SceneError> {
+        scene_format::save(self)
+    }
+
+    /// Load entities saved with `save_to_string` into this World, next to the existing
+    /// ones - returns the saved id -> new id map and the migrations that ran. Nothing is
+    /// created if loading fails
+    pub fn load_from_str(&mut self, text: &str) -> Result<LoadReport, SceneError> {
+        scene_format::load(self, text)
+    }
+
+    /// Write every entity and component as a compact binary snapshot - see `snapshot.rs`
+    /// for the layout. Component types must be registered as serializable
+    pub fn write_snapshot(&self, out: &mut impl std::io::Write) -> Result<(), SnapshotError> {
+        snapshot::write(self, out)
+    }
+
+    /// Replace every entity of this World with the ones of a binary snapshot, keeping
+    /// their ids. The snapshot is fully decoded and its checksum verified first - on error
+    /// the World is left untouched. Components saved with an older schema version are migrated
+    pub fn read_snapshot(
+        &mut self,
+        input: &mut impl std::io::Read,
+    ) -> Result<LoadReport, SnapshotError> {
+        snapshot::read(self, input)
+    }
+
+    /// Capture every entity, component, hierarchy link, layer, tag and prefab link, and the
+    /// entity id allocator, in memory - for rollback and undo
+    ///
+    /// Columns not written since the previous snapshot are shared with it instead of being
+    /// copied again, so taking a snapshot every frame only copies the component types that
+    /// changed. The hierarchy, layers, tags, Name index and prefab links are shared too and
+    /// copied by the first change after the snapshot; the entity id list is always copied.
+    ///
+    /// Component types not registered with `register_clone` (scripts, for instance) cannot be
+    /// copied: they are left out and listed by `WorldSnapshot::skipped_components`, and
+    /// `restore` keeps their current values. The World has no resources (global singletons
+    /// outside of entities) - state kept in systems or elsewhere is not covered either.
+    ///
+    /// ```
+    /// # use ecs_hybrid::*;
+    /// let mut world = World::new();
+    /// let player = world.create_entity();
+    /// world.add_component(player, Health::new(100.0));
+    /// let saved = world.snapshot();
+    ///
+    /// world.write_component::<Health>(player).unwrap().current = 10.0;
+    /// world.create_entity();
+    ///
+    /// world.restore(&saved);
+    /// assert_eq!(world.get_component::<Health>(player).unwrap().current, 100.0);
+    /// assert_eq!(world.entities().count(), 1);
+    /// ```
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let mut columns = Vec::new();
+        let mut skipped = Vec::new();
+        for (type_id, column) in &self.storages {
+            let storage = column.read_recursive();
+            let version = column.version();
+            let mut empty = true;
+            storage.for_each_entity(&mut |_| empty = false);
+            if empty {
+                continue;
+            }
+            let clone = self
+                .registry
+                .get_by_type_id(*type_id)
+                .and_then(|info| info.clone_storage_fn());
+            let Some(clone) = clone else {
+                skipped.push((*type_id, storage.type_name()));
+                continue;
+            };
+            let mut cached = column.snapshot.lock();
+            let shared = match &*cached {
+                Some((cached_version, shared)) if *cached_version == version => shared.clone(),
+                _ => {
+                    let shared: Arc<dyn ComponentStorage> = Arc::from(clone(storage.as_ref()));
+                    *cached = Some((version, shared.clone()));
+                    shared
+                }
+            };
+            columns.push(SnapshotColumn {
+                type_id: *type_id,
+                version,
+                storage: shared,
+                clone,
+            });
+        }
+        WorldSnapshot {
+            next_entity_id: self.allocator.peek(),
+            entities: self.entities.clone(),
+            hierarchy: self.hierarchy.clone(),
+            masks: self.masks.clone(),
+            tags: self.tags.clone(),
+            lookup: self.lookup.read().clone(),
+            prefab_links: self.prefab_links.clone(),
+            columns,
+            skipped,
+        }
+    }
+
+    /// Put the World back in the state captured by `snapshot`
+    ///
+    /// Columns not written since the snapshot was taken are left as they are; the others
+    /// are copied back from it. Component types registered since are kept, but emptied.
+    /// The entity id allocator only moves forward: ids handed out since the snapshot are not
+    /// handed out again.
+    /// Types the snapshot skipped keep their current values on the entities it holds.
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+        let kept: HashSet<u64> = snapshot.entities.iter().copied().collect();
+        for (type_id, column) in &mut self.storages {
+            let skipped = snapshot
+                .skipped
+                .iter()
+                .any(|(skipped, _)| skipped == type_id);
+            match snapshot
+                .columns
+                .iter()
+                .find(|saved| saved.type_id == *type_id)
+            {
+                Some(saved) if saved.version == column.version() => {}
+                Some(saved) => {
+                    *column.get_mut() = (saved.clone)(saved.storage.as_ref());
+                    *column.snapshot.get_mut() = Some((column.version(), saved.storage.clone()));
+                }
+                None => {
+                    let mut entities = Vec::new();
+                    column.get().for_each_entity(&mut |entity| {
+                        if !skipped || !kept.contains(&entity) {
+                            entities.push(entity);
+                        }
+                    });
+                    for entity in entities {
+                        column.remove(entity);
+                    }
+                }
+            }
+        }
+        // Types the snapshot holds that this World has no column for yet
+        for saved in &snapshot.columns {
+            self.storages
+                .entry(saved.type_id)
+                .or_insert_with(|| Column::new((saved.clone)(saved.storage.as_ref())));
+        }
+
+        for entity in &self.entities {
+            if !kept.contains(entity) {
+                self.borrow_trackers.remove_entity(*entity);
+            }
+        }
+        self.allocator.advance_to(snapshot.next_entity_id);
+        self.entities.clone_from(&snapshot.entities);
+        self.entity_set = kept;
+        if self.deterministic {
+            self.entities.sort_unstable();
+        }
+        self.hierarchy = snapshot.hierarchy.clone();
+        self.masks = snapshot.masks.clone();
+        self.tags = snapshot.tags.clone();
+        *self.lookup.get_mut() = snapshot.lookup.clone();
+        self.prefab_links = snapshot.prefab_links.clone();
+    }
+
+    /// Deterministic hash of the simulation state - for lockstep desync detection
+    ///
+    /// Covers the entities (ids, id allocator, hierarchy, active state, layers, tags) and
+    /// the component types registered with `ComponentRegistry::register_state_hash`. Compare
+    /// the per-type breakdown to find which type diverged.
+    ///
+    /// ```
+    /// # use ecs_hybrid::*;
+    /// let mut server = World::new();
+    /// let mut client = World::new();
+    /// for world in [&mut server, &mut client] {
+    ///     world.registry_mut().register_state_hash::<Transform>();
+    ///     world.registry_mut().register_state_hash::<Health>();
+    ///     let player = world.create_entity();
+    ///     world.add_component(player, Transform::new(0.0, 0.0, 0.0));
+    ///     world.add_component(player, Health::new(100.0));
+    /// }
+    /// assert_eq!(server.state_hash(), client.state_hash());
+    ///
+    /// client.write_component::<Health>(0).unwrap().current = 99.0;
+    /// let (server_hash, client_hash) = (server.state_hash(), client.state_hash());
+    /// assert_ne!(server_hash.total, client_hash.total);
+    /// assert_eq!(server_hash.diverged(&client_hash), ["Health"]);
+    /// ```
+    pub fn state_hash(&self) -> StateHash {
+        state_hash::hash(self)
+    }
+
+    /// Changes that turn this World into `other`: spawned and despawned entities, changed
+    /// hierarchy / active state / layers / tags, and added, removed and changed components
+    ///
+    /// Entities are matched by id, so diff Worlds that share their history (a snapshot
+    /// restored, the previous frame, a replicated World). Only serializable component types
+    /// are compared - see `ComponentRegistry::register_serializable`.
+    pub fn diff(&self, other: &World) -> WorldPatch {
+        patch::diff(self, other)
+    }
+
+    /// Apply a patch made by `diff` - the patch is fully validated first, so on error the
+    /// World is left untouched
+    pub fn apply_patch(&mut self, patch: &WorldPatch) -> Result<(), SnapshotError> {
+        patch::apply(self, patch, None)
+    }
+
+    pub(crate) fn next_entity_id(&self) -> u64 {
+        self.allocator.peek()
+    }
+
+    pub(crate) fn advance_next_entity_id(&mut self, next_entity_id: u64) {
+        self.allocator.advance_to(next_entity_id);
+    }
+
+    /// Type-erased `remove_component`
+    pub(crate) fn remove_component_by_type_id(&mut self, type_id: TypeId, entity: u64) {
+        self.update_names(|world| {
+            if type_id == TypeId::of::<Name>() {
+                world.unindex_component::<Name>(entity);
+            }
+            if let Some(column) = world.storages.get_mut(&type_id) {
+                column.remove(entity);
+            }
+        });
+    }
+
+    /// Drop every entity and component (component types stay registered)
+    pub(crate) fn clear_entities(&mut self) {
+        for column in self.storages.values_mut() {
+            let storage = column.get_mut();
+            let mut entities = Vec::new();
+            storage.for_each_entity(&mut |entity| entities.push(entity));
+            for entity in entities {
+                storage.remove(entity);
+            }
+        }
+        for entity in self.entities.drain(..) {
+            self.borrow_trackers.remove_entity(entity);
+        }
+        self.entity_set.clear();
+        self.hierarchy = Arc::default();
+        self.masks = Arc::default();
+        self.tags = Arc::default();
+        let names = self.name_version();
+        *self.lookup.get_mut() = Arc::new(LookupIndex::synced_at(names));
+        self.prefab_links = Arc::default();
+    }
+
+    /// Replace the world's contents with decoded snapshot data - `snapshot::read` has checked
+    /// that ids are below `next_entity_id` and that parents and component owners exist
+    pub(crate) fn restore_decoded(
+        &mut self,
+        next_entity_id: u64,
+        entities: Vec<DecodedEntity>,
+        components: Vec<DecodedComponent>,
+    ) {
+        self.clear_entities();
+        self.allocator.advance_to(next_entity_id);
+        self.entities = entities.iter().map(|entity| entity.id).collect();
+        self.entity_set = self.entities.iter().copied().collect();
+        if self.deterministic {
+            self.entities.sort_unstable();
+        }
+        for entity in &entities {
+            if entity.parent.is_some() {
+                Arc::make_mut(&mut self.hierarchy).set_parent(entity.id, entity.parent);
+            }
+            if !entity.active {
+                Arc::make_mut(&mut self.hierarchy).set_active(entity.id, false);
+            }
+            if entity.layers != LayerMask::DEFAULT {
+                Arc::make_mut(&mut self.masks).set_layers(entity.id, entity.layers);
+            }
+            for tag in &entity.tags {
+                Arc::make_mut(&mut self.tags).add(entity.id, tag);
+            }
+        }
+        for component in components {
+            (component.info.insert_fn().unwrap())(self, component.entity, component.value);
+            if !component.enabled {
+                if let Some(storage) = self.column_mut(component.info.type_id()) {
+                    storage.set_enabled(component.entity, false);
+                }
+            }
+        }
+    }
+
+    /// Read-lock a column by type id
+    pub(crate) fn read_column(
+        &self,
+        type_id: TypeId,
+    ) -> Option<RwLockReadGuard<'_, Box<dyn ComponentStorage>>> {
+        self.storages
+            .get(&type_id)
+            .map(|column| column.read_recursive())
+    }
+
+    pub(crate) fn column_mut(
+        &mut self,
+        type_id: TypeId,
+    ) -> Option<&mut (dyn ComponentStorage + 'static)> {
+        self.storages
+            .get_mut(&type_id)
+            .map(|column| column.get_mut().as_mut())
+    }
+
+    /// Check whether a column exists for `T` (i.e. `T` was ever added)
+    pub fn has_storage<T: 'static>(&self) -> bool {
+        self.storages.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Read-lock the column of `T`
+    pub fn read_storage<T: 'static>(&self) -> Option<StorageReadGuard<'_, T>> {
+        let column = self.storages.get(&TypeId::of::<T>())?;
+        RwLockReadGuard::try_map(column.read_recursive(), |storage| {
+            storage.as_any().downcast_ref::<TypedStorage<T>>()
+        })
+        .ok()
+    }
+
+    /// Column of `T`, for the owning guards of `Entity`
+    pub(crate) fn column<T: 'static>(&self) -> Option<&Column> {
+        self.storages.get(&TypeId::of::<T>())
+    }
+
+    /// Write-lock the column of `T` - only blocks other accesses to `T`
+    pub fn write_storage<T: 'static>(&self) -> Option<StorageWriteGuard<'_, T>> {
+        let column = self.storages.get(&TypeId::of::<T>())?;
+        RwLockWriteGuard::try_map(column.write(), |storage| {
+            storage.as_any_mut().downcast_mut::<TypedStorage<T>>()
+        })
+        .ok()
+    }
+
+    /// Read-lock a component - only blocks writers of the same component type
+    ///
+    /// Returns a guard rather than `&T` (since columns are locked individually), so drop
+    /// it before `&mut World` is needed again.
+    pub fn get_component<T: 'static>(&self, entity: u64) -> Option<ComponentReadGuard<'_, T>> {
+        StorageReadGuard::try_map(self.read_storage::<T>()?, |storage| storage.get(entity)).ok()
+    }
+
+    // pub fn get_component_non_st<T>(&self, entity: u64) -> Option<&T> {
+    //     let type_id = TypeId::of::<T>();
+    //     self.storages
+    //         .get(&type_id)?
+    //         .as_any()
+    //         .downcast_ref::<TypedStorage<T>>()
+    //         .and_then(|storage| storage.get(entity))
+    // }
+
+    /// Write-lock a component through a shared world - only blocks accesses to the same component type
+    pub fn write_component<T: 'static>(&self, entity: u64) -> Option<ComponentWriteGuard<'_, T>> {
+        StorageWriteGuard::try_map(self.write_storage::<T>()?, |storage| {
+            storage.get_mut(entity)
+        })
+        .ok()
+    }
+
+    pub fn get_component_mut<T: 'static>(&mut self, entity: u64) -> Option<&mut T> {
+        self.typed_storage_mut::<T>()
+            .and_then(|storage| storage.get_mut(entity))
+    }
+
+    pub fn remove_component<T: 'static>(&mut self, entity: u64) {
+        self.remove_component_by_type_id(TypeId::of::<T>(), entity);
+    }
+
+    /// Add a component through the column lock only (`&self`)
+    /// Hands the component back if `T` has no column yet - creating one needs `&mut World`
+    pub fn add_component_locked<T: 'static>(&self, entity: u64, component: T) -> Result<(), T> {
+        let Some(mut storage) = self.write_storage::<T>() else {
+            return Err(component);
+        };
+        // Indexed while the column is locked so the index never disagrees with the storage
+        if LookupIndex::is_indexed::<T>() {
+            let mut guard = self.lookup.write();
+            let lookup = Arc::make_mut(&mut guard);
+            lookup.insert(entity, &component);
+            lookup.advance(self.name_version());
         }
+        storage.insert(entity, component);
+        Ok(())
     }
 
-    pub fn insert(&mut self, entity: u64, component: T) {
-        self.components
-            .entry(entity)
-            .or_insert_with(Vec::new)
-            .push(component);
+    /// Remove every instance of a component through the column lock only (`&self`)
+    pub fn remove_component_locked<T: 'static>(&self, entity: u64) {
+        if let Some(mut storage) = self.write_storage::<T>() {
+            if LookupIndex::is_indexed::<T>() {
+                if let Some(components) = storage.get_slice(entity) {
+                    let mut guard = self.lookup.write();
+                    let lookup = Arc::make_mut(&mut guard);
+                    lookup.remove(entity, components);
+                    lookup.advance(self.name_version());
+                }
+            }
+            storage.remove(entity);
+        }
     }
 
-    pub fn get(&self, entity: u64) -> Option<&T> {
-        self.components.get(&entity).and_then(|v| v.first())
+    /// Drop the lookup entries of an entity's `T` components before they are removed
+    fn unindex_component<T: 'static>(&mut self, entity: u64) {
+        if !LookupIndex::is_indexed::<T>() {
+            return;
+        }
+        let Some(column) = self.storages.get_mut(&TypeId::of::<T>()) else {
+            return;
+        };
+        if let Some(storage) = column.get().as_any().downcast_ref::<TypedStorage<T>>() {
+            if let Some(components) = storage.get_slice(entity) {
+                Arc::make_mut(self.lookup.get_mut()).remove(entity, components);
+            }
+        }
     }
 
-    pub fn get_mut(&mut self, entity: u64) -> Option<&mut T> {
-        self.components.get_mut(&entity).and_then(|v| v.first_mut())
+    fn name_version(&self) -> Option<ColumnVersion> {
+        self.storages
+            .get(&TypeId::of::<Name>())
+            .map(Column::version)
     }
 
-    pub fn get_all(&self, entity: u64) -> Vec<T>
-    where
-        T: Clone,
-    {
-        self.components.get(&entity).cloned().unwrap_or_default()
+    /// Apply a change that updates the Name index itself along with the Names it writes -
+    /// the index stays in sync with the column if it was before
+    fn update_names<R>(&mut self, change: impl FnOnce(&mut Self) -> R) -> R {
+        let version = self.name_version();
+        let synced = self.lookup.get_mut().is_synced(version);
+        let result = change(self);
+        if synced {
+            let version = self.name_version();
+            Arc::make_mut(self.lookup.get_mut()).mark_synced(version);
+        }
+        result
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (u64, &T)> {
-        self.components
+    /// The Name index, rebuilt first if Names were changed in place (through
+    /// `write_component`, `query_mut`, `Entity::with`...) since the World last indexed them
+    fn names(&self) -> RwLockReadGuard<'_, Arc<LookupIndex>> {
+        let lookup = self.lookup.read();
+        if lookup.is_synced(self.name_version()) {
+            return lookup;
+        }
+        drop(lookup);
+        // Column before index, like every path that locks both
+        let Some(names) = self.read_storage::<Name>() else {
+            return self.lookup.read();
+        };
+        let version = self.name_version();
+        let mut lookup = self.lookup.write();
+        if !lookup.is_synced(version) {
+            Arc::make_mut(&mut lookup).rebuild(&names, version);
+        }
+        RwLockWriteGuard::downgrade(lookup)
+    }
+
+    /// First active entity (lowest id) with this Name - Unity-like GameObject.Find
+    pub fn find_by_name(&self, name: &str) -> Option<u64> {
+        let lookup = self.names();
+        lookup
+            .with_name(name)
             .iter()
-            .filter_map(|(e, v)| v.first().map(|c| (*e, c)))
+            .copied()
+            .find(|e| self.hierarchy.is_active(*e))
     }
 
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = (u64, &mut T)> {
-        self.components
-            .iter_mut()
-            .filter_map(|(e, v)| v.first_mut().map(|c| (*e, c)))
+    /// Resolve a `"Player/Arm/Weapon"` path - Unity-like GameObject.Find
+    ///
+    /// The first segment is looked up by Name (a leading `/` requires it to be a root
+    /// entity), the rest are matched against children. Only active entities are found.
+    pub fn find_path(&self, path: &str) -> Option<u64> {
+        let (root_only, path) = match path.strip_prefix('/') {
+            Some(path) => (true, path),
+            None => (false, path),
+        };
+        let segments: Vec<&str> = path.split('/').collect();
+        let (first, rest) = segments.split_first()?;
+
+        let names = self.read_storage::<Name>()?;
+        let lookup = self.names();
+        lookup
+            .with_name(first)
+            .iter()
+            .copied()
+            .filter(|e| !root_only || self.hierarchy.parent(*e).is_none())
+            .filter(|e| self.hierarchy.is_active(*e))
+            .find_map(|e| self.descend(&names, e, rest, false))
     }
 
-    /// Get multiple mutable references from different entities
-    /// SAFETY: Caller must ensure entity IDs are unique
-    pub unsafe fn get_many_mut<const N: usize>(
-        &mut self,
-        entities: [u64; N],
-    ) -> [Option<&mut T>; N] {
-        let mut results: [Option<&mut T>; N] = std::array::from_fn(|_| None);
+    /// Resolve a child name or relative path (`"Arm/Weapon"`) - Unity-like Transform.Find,
+    /// which also finds inactive children
+    pub fn find_child(&self, entity: u64, path: &str) -> Option<u64> {
+        let segments: Vec<&str> = path.split('/').collect();
+        let names = self.read_storage::<Name>()?;
+        self.descend(&names, entity, &segments, true)
+    }
 
-        for (i, &entity) in entities.iter().enumerate() {
-            if let Some(vec) = self.components.get_mut(&entity) {
-                if let Some(component) = vec.first_mut() {
-                    // SAFETY: We're transmuting the lifetime to 'static temporarily
-                    // This is safe because we know the caller ensures unique entity IDs
-                    let ptr = component as *mut T;
-                    results[i] = Some(&mut *ptr);
-                }
-            }
-        }
+    /// Follow path segments down the hierarchy, trying every child with a matching name
+    fn descend(
+        &self,
+        names: &TypedStorage<Name>,
+        entity: u64,
+        segments: &[&str],
+        include_inactive: bool,
+    ) -> Option<u64> {
+        let Some((name, rest)) = segments.split_first() else {
+            return Some(entity);
+        };
+        self.hierarchy
+            .children(entity)
+            .iter()
+            .copied()
+            .filter(|child| include_inactive || self.hierarchy.is_active(*child))
+            .filter(|child| {
+                names
+                    .get_slice(*child)
+                    .is_some_and(|n| n.iter().any(|n| n.value == *name))
+            })
+            .find_map(|child| self.descend(names, child, rest, include_inactive))
+    }
 
-        results
+    /// The entity itself or its first descendant (breadth-first) that is active and has an
+    /// enabled `T` - Unity-like GetComponentInChildren
+    pub fn find_in_children<T: 'static>(&self, entity: u64) -> Option<u64> {
+        let storage = self.read_storage::<T>()?;
+        self.hierarchy.subtree(entity).into_iter().find(|e| {
+            self.hierarchy.is_active(*e) && storage.contains(*e) && storage.is_enabled(*e)
+        })
     }
-}
 
-impl<T: Send + Sync + 'static> ComponentStorage for TypedStorage<T> {
-    fn as_any(&self) -> &dyn Any {
-        self
+    /// The entity itself or its closest ancestor that is active and has an enabled `T` -
+    /// Unity-like GetComponentInParent
+    pub fn find_in_parent<T: 'static>(&self, entity: u64) -> Option<u64> {
+        let storage = self.read_storage::<T>()?;
+        std::iter::successors(Some(entity), |e| self.hierarchy.parent(*e)).find(|e| {
+            self.hierarchy.is_active(*e) && storage.contains(*e) && storage.is_enabled(*e)
+        })
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
+    /// Active entities with this tag, sorted by id - Unity-like FindGameObjectsWithTag
+    pub fn find_with_tag(&self, tag: &str) -> Vec<u64> {
+        self.tags
+            .tagged(tag)
+            .iter()
+            .copied()
+            .filter(|e| self.hierarchy.is_active(*e))
+            .collect()
     }
 
-    fn remove(&mut self, entity: u64) {
-        self.components.remove(&entity);
+    /// Active entities on at least one layer of the mask, in creation order
+    pub fn find_in_layers(&self, layers: LayerMask) -> Vec<u64> {
+        self.active_entities()
+            .filter(|e| self.masks.layers(*e).intersects(layers))
+            .collect()
     }
-}
 
-// ---------------------------------------------------------------------------------------------------------------------
+    /// Tag an entity - an entity can have several tags
+    pub fn add_tag(&mut self, entity: u64, tag: &str) {
+        Arc::make_mut(&mut self.tags).add(entity, tag);
+    }
 
-/// The core ECS world - thread-safe and parallel-friendly
-pub struct World {
-    next_entity_id: u64,
-    storages: HashMap<TypeId, Box<dyn ComponentStorage>>,
-    entities: Vec<u64>,
-}
+    pub fn remove_tag(&mut self, entity: u64, tag: &str) {
+        Arc::make_mut(&mut self.tags).remove(entity, tag);
+    }
 
-impl World {
-    pub fn new() -> Self {
-        Self {
-            next_entity_id: 0,
-            storages: HashMap::new(),
-            entities: Vec::new(),
-        }
+    /// Tags of an entity, in the order the tags were first used
+    pub fn tags(&self, entity: u64) -> Vec<&str> {
+        self.tags.of(entity)
     }
 
-    pub fn create_entity(&mut self) -> u64 {
-        let entity = self.next_entity_id;
-        self.next_entity_id += 1;
-        self.entities.push(entity);
-        entity
+    pub fn has_tag(&self, entity: u64, tag: &str) -> bool {
+        self.tags.has(entity, tag)
     }
 
-    /// Create a new entity ID without registering it yet
-    pub fn create_entity_id(&mut self) -> u64 {
-        let id = self.next_entity_id;
-        self.next_entity_id += 1;
-        id
+    /// Put an entity on a set of layers (`LayerMask::DEFAULT` until set)
+    pub fn set_layers(&mut self, entity: u64, layers: LayerMask) {
+        Arc::make_mut(&mut self.masks).set_layers(entity, layers);
     }
 
-    /// Register an entity in the world
-    pub fn register_entity(&mut self, entity: u64) {
-        if !self.entities.contains(&entity) {
-            self.entities.push(entity);
+    pub fn layers(&self, entity: u64) -> LayerMask {
+        self.masks.layers(entity)
+    }
+
+    /// Active entities with an enabled `T`, sorted by id - Unity-like FindObjectsOfType
+    pub fn find_all_with<T: 'static>(&self) -> Vec<u64> {
+        let mut entities: Vec<u64> = self
+            .query::<T>()
+            .map(|query| query.iter().map(|(entity, _)| entity).collect())
+            .unwrap_or_default();
+        entities.sort_unstable();
+        entities
+    }
+
+    /// Enable or disable a component of an entity (with `&mut World`)
+    pub fn set_component_enabled<T: 'static>(&mut self, entity: u64, enabled: bool) {
+        if let Some(storage) = self.typed_storage_mut::<T>() {
+            storage.set_enabled(entity, enabled);
         }
     }
 
-    pub fn add_component<T: Send + Sync + 'static>(&mut self, entity: u64, component: T) {
-        let type_id = TypeId::of::<T>();
+    /// Check whether an entity has `T` and it is enabled
+    pub fn is_component_enabled<T: 'static>(&self, entity: u64) -> bool {
+        self.read_storage::<T>()
+            .is_some_and(|storage| storage.get(entity).is_some() && storage.is_enabled(entity))
+    }
+
+    /// Read-lock the column of `T` for iteration: `world.query::<T>().map(|q| q.iter()...)`
+    pub fn query<T: 'static>(&self) -> Option<Query<'_, T>> {
+        self.query_filtered(QueryFilter::default())
+    }
+
+    /// `query` with an explicit filter (e.g. `QueryFilter::ALL` to see inactive entities)
+    pub fn query_filtered<T: 'static>(&self, filter: QueryFilter) -> Option<Query<'_, T>> {
+        Some(Query {
+            storage: self.read_storage::<T>()?,
+            filter: self.entity_filter(filter),
+            sorted: self.deterministic,
+        })
+    }
+
+    pub fn query_mut<T: 'static>(&mut self) -> Option<impl Iterator<Item = (u64, &mut T)>> {
+        self.query_mut_filtered(QueryFilter::default())
+    }
 
+    /// `query_mut` with an explicit filter
+    pub fn query_mut_filtered<T: 'static>(
+        &mut self,
+        filter: QueryFilter,
+    ) -> Option<impl Iterator<Item = (u64, &mut T)>> {
+        let entity_filter = EntityFilter::new(
+            &self.storages,
+            &self.hierarchy,
+            &self.masks,
+            filter,
+            &[TypeId::of::<T>()],
+        );
+        let sorted = self.deterministic;
         let storage = self
             .storages
-            .entry(type_id)
-            .or_insert_with(|| Box::new(TypedStorage::<T>::new()));
-
-        storage
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut()
             .as_any_mut()
-            .downcast_mut::<TypedStorage<T>>()
-            .unwrap()
-            .insert(entity, component);
+            .downcast_mut::<TypedStorage<T>>()?;
+
+        Some(
+            storage
+                .iter_mut_visible(&entity_filter.filter, sorted)
+                .filter(move |(entity, _)| entity_filter.matches(*entity)),
+        )
     }
 
-    pub fn get_component<T: 'static>(&self, entity: u64) -> Option<&T> {
-        let type_id = TypeId::of::<T>();
-        self.storages
-            .get(&type_id)?
-            .as_any()
-            .downcast_ref::<TypedStorage<T>>()
-            .and_then(|storage| storage.get(entity))
+    fn entity_filter(&self, filter: QueryFilter) -> EntityFilter<'_> {
+        EntityFilter::new(&self.storages, &self.hierarchy, &self.masks, filter, &[])
     }
 
-    // pub fn get_component_non_st<T>(&self, entity: u64) -> Option<&T> {
-    //     let type_id = TypeId::of::<T>();
-    //     self.storages
-    //         .get(&type_id)?
-    //         .as_any()
-    //         .downcast_ref::<TypedStorage<T>>()
-    //         .and_then(|storage| storage.get(entity))
-    // }
+    /// Destroy an entity - its children stay alive and become roots
+    ///
+    /// In a Scene, an entity holding scripts is only queued: it is destroyed right after
+    /// its scripts ran `on_destroy` (at the next `Scene::apply_commands` or end of
+    /// `SystemExecutor::run`), so they can still read its components - like Unity's Destroy,
+    /// which also waits for the end of the frame. Until then it is still alive.
+    pub fn destroy_entity(&mut self, entity: u64) {
+        self.destroy(vec![entity]);
+    }
 
-    pub fn get_component_mut<T: 'static>(&mut self, entity: u64) -> Option<&mut T> {
-        let type_id = TypeId::of::<T>();
-        self.storages
-            .get_mut(&type_id)?
-            .as_any_mut()
-            .downcast_mut::<TypedStorage<T>>()
-            .and_then(|storage| storage.get_mut(entity))
+    /// Destroy an entity and, like Unity, all of its children - queued like `destroy_entity`
+    /// if any of them holds scripts
+    pub fn destroy_recursive(&mut self, entity: u64) {
+        let subtree = self.hierarchy.subtree(entity);
+        self.destroy(subtree);
     }
 
-    pub fn remove_component<T: 'static>(&mut self, entity: u64) {
-        let type_id = TypeId::of::<T>();
-        if let Some(storage) = self.storages.get_mut(&type_id) {
-            storage.remove(entity);
+    /// Destroy entities given parents first - children go first, so none is orphaned
+    fn destroy(&mut self, entities: Vec<u64>) {
+        if self.defer_scripted_destroy && self.has_scripts(&entities) {
+            for entity in entities {
+                if !self.pending_destroy.contains(&entity) {
+                    self.pending_destroy.push(entity);
+                }
+            }
+            return;
+        }
+        for &entity in entities.iter().rev() {
+            self.destroy_entity_now(entity);
         }
     }
 
-    pub fn query<T: 'static>(&self) -> Option<impl Iterator<Item = (u64, &T)>> {
-        let type_id = TypeId::of::<T>();
+    /// Whether any of the entities holds a `Script`
+    fn has_scripts(&mut self, entities: &[u64]) -> bool {
         self.storages
-            .get(&type_id)
-            .and_then(|storage| storage.as_any().downcast_ref::<TypedStorage<T>>())
-            .map(|storage| storage.iter())
+            .get_mut(&TypeId::of::<Script>())
+            .is_some_and(|column| entities.iter().any(|e| column.get().contains(*e)))
     }
 
-    pub fn query_mut<T: 'static>(&mut self) -> Option<impl Iterator<Item = (u64, &mut T)>> {
-        let type_id = TypeId::of::<T>();
-        self.storages
-            .get_mut(&type_id)
-            .and_then(|storage| storage.as_any_mut().downcast_mut::<TypedStorage<T>>())
-            .map(|storage| storage.iter_mut())
+    /// Queue destroyed entities with scripts instead of destroying them (see `destroy_entity`)
+    pub(crate) fn set_defer_scripted_destroy(&mut self, defer: bool) {
+        self.defer_scripted_destroy = defer;
     }
 
-    pub fn destroy_entity(&mut self, entity: u64) {
-        // Remove from all storages
-        for storage in self.storages.values_mut() {
-            storage.remove(entity);
-        }
+    /// Entities queued by `destroy_entity`, parents before children - still alive until
+    /// `destroy_entity_now`
+    pub(crate) fn take_pending_destroy(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.pending_destroy)
+    }
+
+    /// `destroy_entity` without waiting for scripts
+    pub(crate) fn destroy_entity_now(&mut self, entity: u64) {
+        self.update_names(|world| {
+            world.unindex_component::<Name>(entity);
+            // Remove from all storages
+            for column in world.storages.values_mut() {
+                column.remove(entity);
+            }
+        });
         // Remove from entity list
         self.entities.retain(|e| *e != entity);
+        self.entity_set.remove(&entity);
+        self.borrow_trackers.remove_entity(entity);
+        Arc::make_mut(&mut self.hierarchy).remove(entity);
+        Arc::make_mut(&mut self.masks).remove(entity);
+        Arc::make_mut(&mut self.tags).remove_entity(entity);
+        Arc::make_mut(&mut self.prefab_links).remove(&entity);
+    }
+
+    /// Parent an entity to another (or detach it with `None`)
+    /// Returns false if either entity is not alive, or if the parent is the entity itself
+    /// or one of its descendants
+    pub fn set_parent(&mut self, child: u64, parent: Option<u64>) -> bool {
+        if !self.contains_entity(child) || parent.is_some_and(|p| !self.contains_entity(p)) {
+            return false;
+        }
+        Arc::make_mut(&mut self.hierarchy).set_parent(child, parent)
+    }
+
+    pub fn parent(&self, entity: u64) -> Option<u64> {
+        self.hierarchy.parent(entity)
+    }
+
+    pub fn children(&self, entity: u64) -> &[u64] {
+        self.hierarchy.children(entity)
+    }
+
+    /// Unity-like SetActive - inactive entities (and their descendants) are skipped by queries
+    /// Ignored for entities that are not alive
+    pub fn set_active(&mut self, entity: u64, active: bool) {
+        if self.contains_entity(entity) {
+            Arc::make_mut(&mut self.hierarchy).set_active(entity, active);
+        }
+    }
+
+    /// Own active flag, ignoring ancestors (Unity's activeSelf)
+    pub fn is_active_self(&self, entity: u64) -> bool {
+        self.hierarchy.is_active_self(entity)
+    }
+
+    /// Active itself and through every ancestor (Unity's activeInHierarchy)
+    pub fn is_active(&self, entity: u64) -> bool {
+        self.hierarchy.is_active(entity)
     }
 
-    /// Get all entities
+    /// Entity-level check of a query filter
+    pub fn matches_filter(&self, entity: u64, filter: QueryFilter) -> bool {
+        self.entity_filter(filter).matches(entity)
+    }
+
+    /// Runtime borrow trackers shared by every `Entity` handle into this world
+    pub(crate) fn borrow_trackers(&self) -> Arc<BorrowTrackers> {
+        self.borrow_trackers.clone()
+    }
+
+    /// Runtime borrow tracker of one (entity, component type)
+    pub(crate) fn borrow_tracker<T: 'static>(&self, entity: u64) -> Arc<BorrowTracker> {
+        self.borrow_trackers.get::<T>(entity)
+    }
+
+    /// Check whether an entity is registered (i.e. created and not destroyed)
+    pub fn contains_entity(&self, entity: u64) -> bool {
+        self.entity_set.contains(&entity)
+    }
+
+    /// Get all entities, in creation order (id order in deterministic mode)
     pub fn entities(&self) -> impl Iterator<Item = u64> + '_ {
         self.entities.iter().copied()
     }
 
+    /// Get entities that are active in the hierarchy
+    pub fn active_entities(&self) -> impl Iterator<Item = u64> + '_ {
+        self.entities
+            .iter()
+            .copied()
+            .filter(|e| self.hierarchy.is_active(*e))
+    }
+
     /// Access all components of a specific type for an entity through a closure (no cloning)
     pub fn with_components<T: 'static, R, F>(&self, entity: u64, f: F) -> Option<R>
     where
         F: FnOnce(&[T]) -> R,
     {
-        let type_id = TypeId::of::<T>();
-        self.storages.get(&type_id).and_then(|storage| {
-            storage
-                .as_any()
-                .downcast_ref::<TypedStorage<T>>()
-                .and_then(|typed_storage| {
-                    typed_storage
-                        .components
-                        .get(&entity)
-                        .map(|vec| f(vec.as_slice()))
-                })
-        })
+        self.read_storage::<T>()
+            .and_then(|typed_storage| typed_storage.get_slice(entity).map(f))
     }
 
     /// Query two components - read-only for both
-    pub fn query2<T1: 'static, T2: 'static>(&self) -> impl Iterator<Item = (&T1, &T2)> + '_ {
-        let type_id1 = TypeId::of::<T1>();
-        let type_id2 = TypeId::of::<T2>();
-
-        let storage1 = self
-            .storages
-            .get(&type_id1)
-            .and_then(|s| s.as_any().downcast_ref::<TypedStorage<T1>>());
-
-        let storage2 = self
-            .storages
-            .get(&type_id2)
-            .and_then(|s| s.as_any().downcast_ref::<TypedStorage<T2>>());
+    pub fn query2<T1: 'static, T2: 'static>(&self) -> Query2<'_, T1, T2> {
+        self.query2_filtered(QueryFilter::default())
+    }
 
-        // Iterate over entities that have both components
-        storage1
-            .into_iter()
-            .flat_map(move |s1| s1.iter())
-            .filter_map(move |(entity, comp1)| {
-                storage2.and_then(|s2| s2.get(entity).map(|comp2| (comp1, comp2)))
-            })
+    /// `query2` with an explicit filter
+    pub fn query2_filtered<T1: 'static, T2: 'static>(
+        &self,
+        filter: QueryFilter,
+    ) -> Query2<'_, T1, T2> {
+        Query2 {
+            storage1: self.read_storage::<T1>(),
+            storage2: self.read_storage::<T2>(),
+            filter: self.entity_filter(filter),
+            sorted: self.deterministic,
+        }
     }
 
     /// Query two components - first mutable, second read-only
     pub fn query2_mut<T1: 'static, T2: 'static>(&mut self) -> Vec<(&mut T1, &T2)> {
+        self.query2_mut_filtered(QueryFilter::default())
+    }
+
+    /// `query2_mut` with an explicit filter
+    pub fn query2_mut_filtered<T1: 'static, T2: 'static>(
+        &mut self,
+        filter: QueryFilter,
+    ) -> Vec<(&mut T1, &T2)> {
+        let entity_filter = EntityFilter::new(
+            &self.storages,
+            &self.hierarchy,
+            &self.masks,
+            filter,
+            &[TypeId::of::<T1>(), TypeId::of::<T2>()],
+        );
         let type_id1 = TypeId::of::<T1>();
         let type_id2 = TypeId::of::<T2>();
 
@@ -264,11 +1996,11 @@ impl World {
         let storage1_ptr = self
             .storages
             .get_mut(&type_id1)
-            .map(|s| s.as_mut() as *mut dyn ComponentStorage);
+            .map(|s| s.get_mut().as_mut() as *mut dyn ComponentStorage);
         let storage2_ptr = self
             .storages
-            .get(&type_id2)
-            .map(|s| s.as_ref() as *const dyn ComponentStorage);
+            .get_mut(&type_id2)
+            .map(|s| s.get_mut().as_mut() as *mut dyn ComponentStorage);
 
         if storage1_ptr.is_none() || storage2_ptr.is_none() {
             return Vec::new();
@@ -289,7 +2021,10 @@ impl World {
 
             // Collect entities that have both components
             let mut results = Vec::new();
-            for (entity, comp1) in s1.iter_mut() {
+            for (entity, comp1) in s1.iter_mut_visible(&entity_filter.filter, self.deterministic) {
+                if !entity_filter.matches_component(s2, entity) {
+                    continue;
+                }
                 if let Some(comp2) = s2.get(entity) {
                     results.push((comp1, comp2));
                 }
@@ -300,6 +2035,21 @@ impl World {
 
     /// Query two components - both mutable
     pub fn query2_mut_mut<T1: 'static, T2: 'static>(&mut self) -> Vec<(&mut T1, &mut T2)> {
+        self.query2_mut_mut_filtered(QueryFilter::default())
+    }
+
+    /// `query2_mut_mut` with an explicit filter
+    pub fn query2_mut_mut_filtered<T1: 'static, T2: 'static>(
+        &mut self,
+        filter: QueryFilter,
+    ) -> Vec<(&mut T1, &mut T2)> {
+        let entity_filter = EntityFilter::new(
+            &self.storages,
+            &self.hierarchy,
+            &self.masks,
+            filter,
+            &[TypeId::of::<T1>(), TypeId::of::<T2>()],
+        );
         let type_id1 = TypeId::of::<T1>();
         let type_id2 = TypeId::of::<T2>();
 
@@ -307,11 +2057,11 @@ impl World {
         let storage1_ptr = self
             .storages
             .get_mut(&type_id1)
-            .map(|s| s.as_mut() as *mut dyn ComponentStorage);
+            .map(|s| s.get_mut().as_mut() as *mut dyn ComponentStorage);
         let storage2_ptr = self
             .storages
             .get_mut(&type_id2)
-            .map(|s| s.as_mut() as *mut dyn ComponentStorage);
+            .map(|s| s.get_mut().as_mut() as *mut dyn ComponentStorage);
 
         if storage1_ptr.is_none() || storage2_ptr.is_none() {
             return Vec::new();
@@ -324,34 +2074,27 @@ impl World {
             let s1 = (*storage1_ptr)
                 .as_any_mut()
                 .downcast_mut::<TypedStorage<T1>>()
-                .unwrap();
+                .unwrap() as *mut TypedStorage<T1>;
             let s2 = (*storage2_ptr)
                 .as_any_mut()
                 .downcast_mut::<TypedStorage<T2>>()
-                .unwrap();
+                .unwrap() as *mut TypedStorage<T2>;
 
             // First collect entity IDs that have both components
-            let entities: Vec<u64> = s1
-                .iter()
-                .filter_map(|(entity, _)| {
-                    if s2.get(entity).is_some() {
-                        Some(entity)
-                    } else {
-                        None
-                    }
+            let entities: Vec<u64> = (*s1)
+                .iter_sorted(self.deterministic)
+                .map(|(entity, _)| entity)
+                .filter(|&entity| {
+                    (*s2).contains(entity)
+                        && entity_filter.matches_component(&*s1, entity)
+                        && (*s2).visible(entity, &entity_filter.filter)
                 })
                 .collect();
 
             // Now get mutable references using raw pointer access
             let mut results = Vec::new();
-            let s1_map = &mut s1.components as *mut HashMap<u64, Vec<T1>>;
-            let s2_map = &mut s2.components as *mut HashMap<u64, Vec<T2>>;
-
             for entity in entities {
-                let comp1 = (*s1_map).get_mut(&entity).and_then(|v| v.first_mut());
-                let comp2 = (*s2_map).get_mut(&entity).and_then(|v| v.first_mut());
-
-                if let (Some(c1), Some(c2)) = (comp1, comp2) {
+                if let (Some(c1), Some(c2)) = ((*s1).get_mut(entity), (*s2).get_mut(entity)) {
                     results.push((c1, c2));
                 }
             }
@@ -360,44 +2103,43 @@ impl World {
     }
 
     /// Query three components - all read-only
-    pub fn query3<T1: 'static, T2: 'static, T3: 'static>(
-        &self,
-    ) -> impl Iterator<Item = (&T1, &T2, &T3)> + '_ {
-        let type_id1 = TypeId::of::<T1>();
-        let type_id2 = TypeId::of::<T2>();
-        let type_id3 = TypeId::of::<T3>();
-
-        let storage1 = self
-            .storages
-            .get(&type_id1)
-            .and_then(|s| s.as_any().downcast_ref::<TypedStorage<T1>>());
-
-        let storage2 = self
-            .storages
-            .get(&type_id2)
-            .and_then(|s| s.as_any().downcast_ref::<TypedStorage<T2>>());
-
-        let storage3 = self
-            .storages
-            .get(&type_id3)
-            .and_then(|s| s.as_any().downcast_ref::<TypedStorage<T3>>());
+    pub fn query3<T1: 'static, T2: 'static, T3: 'static>(&self) -> Query3<'_, T1, T2, T3> {
+        self.query3_filtered(QueryFilter::default())
+    }
 
-        storage1
-            .into_iter()
-            .flat_map(move |s1| s1.iter())
-            .filter_map(move |(entity, comp1)| {
-                storage2.and_then(|s2| {
-                    s2.get(entity).and_then(|comp2| {
-                        storage3.and_then(|s3| s3.get(entity).map(|comp3| (comp1, comp2, comp3)))
-                    })
-                })
-            })
+    /// `query3` with an explicit filter
+    pub fn query3_filtered<T1: 'static, T2: 'static, T3: 'static>(
+        &self,
+        filter: QueryFilter,
+    ) -> Query3<'_, T1, T2, T3> {
+        Query3 {
+            storage1: self.read_storage::<T1>(),
+            storage2: self.read_storage::<T2>(),
+            storage3: self.read_storage::<T3>(),
+            filter: self.entity_filter(filter),
+            sorted: self.deterministic,
+        }
     }
 
     /// Query three components - all mutable
     pub fn query3_mut<T1: 'static, T2: 'static, T3: 'static>(
         &mut self,
     ) -> Vec<(&mut T1, &mut T2, &mut T3)> {
+        self.query3_mut_filtered(QueryFilter::default())
+    }
+
+    /// `query3_mut` with an explicit filter
+    pub fn query3_mut_filtered<T1: 'static, T2: 'static, T3: 'static>(
+        &mut self,
+        filter: QueryFilter,
+    ) -> Vec<(&mut T1, &mut T2, &mut T3)> {
+        let entity_filter = EntityFilter::new(
+            &self.storages,
+            &self.hierarchy,
+            &self.masks,
+            filter,
+            &[TypeId::of::<T1>(), TypeId::of::<T2>(), TypeId::of::<T3>()],
+        );
         let type_id1 = TypeId::of::<T1>();
         let type_id2 = TypeId::of::<T2>();
         let type_id3 = TypeId::of::<T3>();
@@ -406,15 +2148,15 @@ impl World {
         let storage1_ptr = self
             .storages
             .get_mut(&type_id1)
-            .map(|s| s.as_mut() as *mut dyn ComponentStorage);
+            .map(|s| s.get_mut().as_mut() as *mut dyn ComponentStorage);
         let storage2_ptr = self
             .storages
             .get_mut(&type_id2)
-            .map(|s| s.as_mut() as *mut dyn ComponentStorage);
+            .map(|s| s.get_mut().as_mut() as *mut dyn ComponentStorage);
         let storage3_ptr = self
             .storages
             .get_mut(&type_id3)
-            .map(|s| s.as_mut() as *mut dyn ComponentStorage);
+            .map(|s| s.get_mut().as_mut() as *mut dyn ComponentStorage);
 
         if storage1_ptr.is_none() || storage2_ptr.is_none() || storage3_ptr.is_none() {
             return Vec::new();
@@ -428,38 +2170,35 @@ impl World {
             let s1 = (*storage1_ptr)
                 .as_any_mut()
                 .downcast_mut::<TypedStorage<T1>>()
-                .unwrap();
+                .unwrap() as *mut TypedStorage<T1>;
             let s2 = (*storage2_ptr)
                 .as_any_mut()
                 .downcast_mut::<TypedStorage<T2>>()
-                .unwrap();
+                .unwrap() as *mut TypedStorage<T2>;
             let s3 = (*storage3_ptr)
                 .as_any_mut()
                 .downcast_mut::<TypedStorage<T3>>()
-                .unwrap();
+                .unwrap() as *mut TypedStorage<T3>;
 
             // First collect entity IDs that have all three components
-            let entities: Vec<u64> = s1
-                .iter()
-                .filter_map(|(entity, _)| {
-                    if s2.get(entity).is_some() && s3.get(entity).is_some() {
-                        Some(entity)
-                    } else {
-                        None
-                    }
+            let entities: Vec<u64> = (*s1)
+                .iter_sorted(self.deterministic)
+                .map(|(entity, _)| entity)
+                .filter(|&entity| {
+                    (*s2).contains(entity)
+                        && (*s3).contains(entity)
+                        && entity_filter.matches_component(&*s1, entity)
+                        && (*s2).visible(entity, &entity_filter.filter)
+                        && (*s3).visible(entity, &entity_filter.filter)
                 })
                 .collect();
 
             // Now get mutable references using raw pointer access
             let mut results = Vec::new();
-            let s1_map = &mut s1.components as *mut HashMap<u64, Vec<T1>>;
-            let s2_map = &mut s2.components as *mut HashMap<u64, Vec<T2>>;
-            let s3_map = &mut s3.components as *mut HashMap<u64, Vec<T3>>;
-
             for entity in entities {
-                let comp1 = (*s1_map).get_mut(&entity).and_then(|v| v.first_mut());
-                let comp2 = (*s2_map).get_mut(&entity).and_then(|v| v.first_mut());
-                let comp3 = (*s3_map).get_mut(&entity).and_then(|v| v.first_mut());
+                let comp1 = (*s1).get_mut(entity);
+                let comp2 = (*s2).get_mut(entity);
+                let comp3 = (*s3).get_mut(entity);
 
                 if let (Some(c1), Some(c2), Some(c3)) = (comp1, comp2, comp3) {
                     results.push((c1, c2, c3));
@@ -469,3 +2208,92 @@ impl World {
         }
     }
 }
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Read-locked column for a single-component query
+pub struct Query<'w, T: 'static> {
+    storage: StorageReadGuard<'w, T>,
+    filter: EntityFilter<'w>,
+    /// Iterate in entity id order (`World::set_deterministic`)
+    sorted: bool,
+}
+
+impl<T: 'static> Query<'_, T> {
+    /// Iterate over (entity, component) pairs passing the filter
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &T)> + '_ {
+        let storage = &*self.storage;
+        storage
+            .iter_sorted(self.sorted)
+            .filter(move |(entity, _)| self.filter.matches_component(storage, *entity))
+    }
+}
+
+/// Read-locked columns for a two-component query
+pub struct Query2<'w, T1: 'static, T2: 'static> {
+    storage1: Option<StorageReadGuard<'w, T1>>,
+    storage2: Option<StorageReadGuard<'w, T2>>,
+    filter: EntityFilter<'w>,
+    sorted: bool,
+}
+
+impl<T1: 'static, T2: 'static> Query2<'_, T1, T2> {
+    /// Iterate over entities that have both components
+    pub fn iter(&self) -> impl Iterator<Item = (&T1, &T2)> + '_ {
+        let storage2 = self.storage2.as_deref();
+        self.storage1
+            .as_deref()
+            .into_iter()
+            .flat_map(|s1| {
+                s1.iter_sorted(self.sorted)
+                    .map(move |(entity, comp1)| (s1, entity, comp1))
+            })
+            .filter(|(s1, entity, _)| self.filter.matches_component(s1, *entity))
+            .filter_map(move |(_, entity, comp1)| {
+                storage2
+                    .filter(|s2| s2.visible(entity, &self.filter.filter))
+                    .and_then(|s2| s2.get(entity).map(|comp2| (comp1, comp2)))
+            })
+    }
+}
+
+/// Read-locked columns for a three-component query
+pub struct Query3<'w, T1: 'static, T2: 'static, T3: 'static> {
+    storage1: Option<StorageReadGuard<'w, T1>>,
+    storage2: Option<StorageReadGuard<'w, T2>>,
+    storage3: Option<StorageReadGuard<'w, T3>>,
+    filter: EntityFilter<'w>,
+    sorted: bool,
+}
+
+impl<T1: 'static, T2: 'static, T3: 'static> Query3<'_, T1, T2, T3> {
+    /// Iterate over entities that have all three components
+    pub fn iter(&self) -> impl Iterator<Item = (&T1, &T2, &T3)> + '_ {
+        let filter = &self.filter;
+        let storage2 = self.storage2.as_deref();
+        let storage3 = self.storage3.as_deref();
+        self.storage1
+            .as_deref()
+            .into_iter()
+            .flat_map(|s1| {
+                s1.iter_sorted(self.sorted)
+                    .map(move |(entity, comp1)| (s1, entity, comp1))
+            })
+            .filter(move |(s1, entity, _)| {
+                filter.matches_component(s1, *entity)
+                    && storage2.is_some_and(|s2| s2.visible(*entity, &filter.filter))
+                    && storage3.is_some_and(|s3| s3.visible(*entity, &filter.filter))
+            })
+            .filter_map(move |(_, entity, comp1)| {
+                let comp2 = storage2?.get(entity)?;
+                let comp3 = storage3?.get(entity)?;
+                Some((comp1, comp2, comp3))
+            })
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
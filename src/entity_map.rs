@@ -0,0 +1,63 @@
+/// Entity id remapping - used when entities are copied into fresh ids (prefabs, loading)
+use std::collections::HashMap;
+
+/// Old entity id -> new entity id
+#[derive(Debug, Default, Clone)]
+pub struct EntityMap {
+    map: HashMap<u64, u64>,
+}
+
+impl EntityMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, from: u64, to: u64) {
+        self.map.insert(from, to);
+    }
+
+    pub fn get(&self, from: u64) -> Option<u64> {
+        self.map.get(&from).copied()
+    }
+
+    /// New id of an entity - ids outside the map (e.g. references to entities that were
+    /// not copied) are kept as they are
+    pub fn map(&self, from: u64) -> u64 {
+        self.get(from).unwrap_or(from)
+    }
+
+    pub fn remove(&mut self, from: u64) -> Option<u64> {
+        self.map.remove(&from)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.map.iter().map(|(from, to)| (*from, *to))
+    }
+}
+
+/// Component holding entity ids that must follow the entities when they are copied
+///
+/// ```
+/// # use ecs_hybrid::*;
+/// #[derive(Clone)]
+/// struct Target {
+///     entity: u64,
+/// }
+///
+/// impl MapEntities for Target {
+///     fn map_entities(&mut self, map: &EntityMap) {
+///         self.entity = map.map(self.entity);
+///     }
+/// }
+/// ```
+pub trait MapEntities {
+    fn map_entities(&mut self, map: &EntityMap);
+}
@@ -0,0 +1,231 @@
+/// Entity views - access many components of one entity under a single world lock
+use crate::borrow_tracker::BorrowGuard;
+use crate::ecs_core::{ComponentReadGuard, ComponentWriteGuard, World};
+use parking_lot::RwLockReadGuard;
+use std::any::TypeId;
+use std::ops::{Deref, DerefMut};
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Component borrowed immutably from an entity view
+pub struct ViewRef<'w, T> {
+    inner: ComponentReadGuard<'w, T>,
+    _borrow_guard: BorrowGuard,
+}
+
+impl<'w, T: 'static> ViewRef<'w, T> {
+    fn lock(world: &'w World, entity: u64) -> Option<Self> {
+        // Acquire read borrow first so a conflicting borrow panics instead of deadlocking
+        let borrow_guard = world.borrow_tracker::<T>(entity).borrow_read();
+        Some(Self {
+            inner: world.get_component::<T>(entity)?,
+            _borrow_guard: borrow_guard,
+        })
+    }
+}
+
+impl<T> Deref for ViewRef<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+/// Component borrowed mutably from an entity view
+pub struct ViewMut<'w, T> {
+    inner: ComponentWriteGuard<'w, T>,
+    _borrow_guard: BorrowGuard,
+}
+
+impl<'w, T: 'static> ViewMut<'w, T> {
+    fn lock(world: &'w World, entity: u64) -> Option<Self> {
+        // Acquire write borrow first so a conflicting borrow panics instead of deadlocking
+        let borrow_guard = world.borrow_tracker::<T>(entity).borrow_write();
+        Some(Self {
+            inner: world.write_component::<T>(entity)?,
+            _borrow_guard: borrow_guard,
+        })
+    }
+}
+
+impl<T> Deref for ViewMut<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for ViewMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Read-only view of an entity - holds one shared world lock for any number of components
+///
+/// Usage:
+/// ```
+/// # use ecs_hybrid::*;
+/// # let scene = Scene::new();
+/// # let entity = scene.instantiate();
+/// # entity.add_component(Name::new("Player")).add_component(Health::new(100.0));
+/// let view = entity.read();
+/// let (name, health) = (view.get::<Name>().unwrap(), view.get::<Health>().unwrap());
+/// println!("{} has {} HP", name.value, health.current);
+/// ```
+pub struct EntityRef<'a> {
+    id: u64,
+    world: RwLockReadGuard<'a, World>,
+}
+
+impl<'a> EntityRef<'a> {
+    pub(crate) fn new(id: u64, world: RwLockReadGuard<'a, World>) -> Self {
+        Self { id, world }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Borrow a component immutably (panics if it is already borrowed mutably)
+    pub fn get<T: 'static>(&self) -> Option<ViewRef<'_, T>> {
+        ViewRef::lock(&self.world, self.id)
+    }
+
+    /// Check if component exists
+    pub fn has<T: 'static>(&self) -> bool {
+        self.world.get_component::<T>(self.id).is_some()
+    }
+}
+
+/// Mutable view of an entity - holds a shared world lock, and each `get_mut` write-locks
+/// only the column of its component type, so other threads keep working on other types
+///
+/// Adding or removing components needs the whole world: use `Entity::add_component` /
+/// `Entity::remove_component`, or their deferred versions, once the view is dropped.
+pub struct EntityMut<'a> {
+    id: u64,
+    world: RwLockReadGuard<'a, World>,
+}
+
+impl<'a> EntityMut<'a> {
+    pub(crate) fn new(id: u64, world: RwLockReadGuard<'a, World>) -> Self {
+        Self { id, world }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Borrow a component immutably (panics if it is already borrowed mutably)
+    pub fn get<T: 'static>(&self) -> Option<ViewRef<'_, T>> {
+        ViewRef::lock(&self.world, self.id)
+    }
+
+    /// Borrow a component mutably (panics if it is already borrowed) - when borrowing
+    /// several, take them in `TypeId` order (see `World`)
+    pub fn get_mut<T: 'static>(&self) -> Option<ViewMut<'_, T>> {
+        ViewMut::lock(&self.world, self.id)
+    }
+
+    /// Check if component exists
+    pub fn has<T: 'static>(&self) -> bool {
+        self.world.get_component::<T>(self.id).is_some()
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Set of component borrows for `Entity::with` - `&T`, `&mut T` or tuples of them
+pub trait ComponentAccess {
+    /// Locks held while the closure runs
+    type Guard<'w>;
+    /// What the closure receives
+    type Item<'g>;
+
+    /// Borrow and lock every component - `None` if any is missing
+    fn lock(world: &World, entity: u64) -> Option<Self::Guard<'_>>;
+
+    fn item<'g>(guard: &'g mut Self::Guard<'_>) -> Self::Item<'g>;
+}
+
+/// A single component borrow (`&T` or `&mut T`) - the elements of a `ComponentAccess` tuple
+pub trait ComponentBorrow: ComponentAccess {
+    /// Type whose column is locked - tuples lock their elements in this order
+    fn component_type() -> TypeId;
+}
+
+impl<T: 'static> ComponentAccess for &T {
+    type Guard<'w> = ViewRef<'w, T>;
+    type Item<'g> = &'g T;
+
+    fn lock(world: &World, entity: u64) -> Option<Self::Guard<'_>> {
+        ViewRef::lock(world, entity)
+    }
+
+    fn item<'g>(guard: &'g mut Self::Guard<'_>) -> Self::Item<'g> {
+        guard
+    }
+}
+
+impl<T: 'static> ComponentBorrow for &T {
+    fn component_type() -> TypeId {
+        TypeId::of::<T>()
+    }
+}
+
+impl<T: 'static> ComponentAccess for &mut T {
+    type Guard<'w> = ViewMut<'w, T>;
+    type Item<'g> = &'g mut T;
+
+    fn lock(world: &World, entity: u64) -> Option<Self::Guard<'_>> {
+        ViewMut::lock(world, entity)
+    }
+
+    fn item<'g>(guard: &'g mut Self::Guard<'_>) -> Self::Item<'g> {
+        guard
+    }
+}
+
+impl<T: 'static> ComponentBorrow for &mut T {
+    fn component_type() -> TypeId {
+        TypeId::of::<T>()
+    }
+}
+
+macro_rules! impl_component_access_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: ComponentBorrow),+> ComponentAccess for ($($name,)+) {
+            type Guard<'w> = ($($name::Guard<'w>,)+);
+            type Item<'g> = ($($name::Item<'g>,)+);
+
+            #[allow(non_snake_case)]
+            fn lock(world: &World, entity: u64) -> Option<Self::Guard<'_>> {
+                // Columns are always locked in `TypeId` order, whatever the tuple order
+                let mut order = [$(($name::component_type(), $index)),+];
+                order.sort_unstable();
+                $(let mut $name = None;)+
+                for (_, index) in order {
+                    match index {
+                        $($index => $name = Some($name::lock(world, entity)?),)+
+                        _ => unreachable!(),
+                    }
+                }
+                Some(($($name?,)+))
+            }
+
+            #[allow(non_snake_case)]
+            fn item<'g>(guard: &'g mut Self::Guard<'_>) -> Self::Item<'g> {
+                let ($($name,)+) = guard;
+                ($($name::item($name),)+)
+            }
+        }
+    };
+}
+
+impl_component_access_tuple!(A 0);
+impl_component_access_tuple!(A 0, B 1);
+impl_component_access_tuple!(A 0, B 1, C 2);
+impl_component_access_tuple!(A 0, B 1, C 2, D 3);
@@ -1,117 +1,85 @@
 /// Unity-like Entity API - provides familiar OOP interface over ECS
-use crate::ecs_core::World;
-use crate::{command_buffer::CommandBuffer, Transform};
-use parking_lot::{
-    MappedRwLockReadGuard, MappedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
-};
-use std::cell::Cell;
+use crate::behaviour::{self, Behaviour, Script, ScriptScheduler};
+use crate::borrow_tracker::{BorrowGuard, BorrowTrackers};
+use crate::command_buffer::CommandBuffer;
+use crate::coroutine::{self, Coroutine, CoroutineId, CoroutineScheduler};
+use crate::ecs_core::{ArcColumnReadGuard, ArcColumnWriteGuard, CloneError, TypedStorage, World};
+use crate::entity_view::{ComponentAccess, EntityMut, EntityRef};
+use crate::layers::LayerMask;
+use crate::migration::LoadReport;
+use crate::prefab::{Prefab, PrefabHandle};
+use crate::reflect::{ReflectError, Value};
+use crate::scene_format::SceneError;
+use crate::Name;
+use parking_lot::{MappedRwLockReadGuard, Mutex, RwLock, RwLockReadGuard};
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 // ---------------------------------------------------------------------------------------------------------------------
 
-/// Runtime borrow tracking for detecting deadlocks at runtime
-#[derive(Default)]
-struct BorrowTracker {
-    read_count: Cell<usize>,
-    write_count: Cell<usize>,
-}
-
-impl BorrowTracker {
-    fn new() -> Self {
-        Self {
-            read_count: Cell::new(0),
-            write_count: Cell::new(0),
-        }
-    }
-
-    fn borrow_read(&self) -> BorrowGuard<'_> {
-        if self.write_count.get() > 0 {
-            panic!(
-                "Cannot borrow component immutably: already borrowed mutably ({} active write borrows)",
-                self.write_count.get()
-            );
-        }
-        self.read_count.set(self.read_count.get() + 1);
-        BorrowGuard {
-            tracker: self,
-            is_write: false,
-        }
-    }
-
-    fn borrow_write(&self) -> BorrowGuard<'_> {
-        if self.read_count.get() > 0 {
-            panic!(
-                "Cannot borrow component mutably: already borrowed immutably ({} active read borrows)",
-                self.read_count.get()
-            );
-        }
-        if self.write_count.get() > 0 {
-            panic!(
-                "Cannot borrow component mutably: already borrowed mutably ({} active write borrows)",
-                self.write_count.get()
-            );
-        }
-        self.write_count.set(1);
-        BorrowGuard {
-            tracker: self,
-            is_write: true,
-        }
-    }
-}
-
-struct BorrowGuard<'a> {
-    tracker: &'a BorrowTracker,
-    is_write: bool,
-}
-
-impl Drop for BorrowGuard<'_> {
-    fn drop(&mut self) {
-        if self.is_write {
-            self.tracker.write_count.set(0);
-        } else {
-            self.tracker
-                .read_count
-                .set(self.tracker.read_count.get() - 1);
-        }
-    }
-}
-
 pub type RawComponentRef<'a, T> = MappedRwLockReadGuard<'a, T>;
 
+/// Column read lock + shared world lock
+///
+/// The column guard owns its lock (it does not borrow from `_world`), and the component
+/// is looked up again on each access. It is declared first so the column is unlocked
+/// before the world, which keeps `&mut World` the sole owner of every column.
 pub struct ComponentRefer<'a, T> {
-    inner: MappedRwLockReadGuard<'a, T>,
-    _borrow_guard: BorrowGuard<'a>,
+    column: ArcColumnReadGuard,
+    entity: u64,
+    _world: RwLockReadGuard<'a, World>,
+    _borrow_guard: BorrowGuard,
+    _marker: PhantomData<T>,
 }
 
+/// Column write lock + shared world lock - see `ComponentRefer`
 pub struct ComponentReferMut<'a, T> {
-    inner: MappedRwLockWriteGuard<'a, T>,
-    _borrow_guard: BorrowGuard<'a>,
+    column: ArcColumnWriteGuard,
+    entity: u64,
+    _world: RwLockReadGuard<'a, World>,
+    _borrow_guard: BorrowGuard,
+    _marker: PhantomData<T>,
 }
 
-impl<'a, T> Deref for ComponentRefer<'a, T> {
+/// The column stays locked for the guard's lifetime, so the component cannot go away
+const COMPONENT_PRESENT: &str = "component removed while its column was locked";
+
+impl<'a, T: 'static> Deref for ComponentRefer<'a, T> {
     type Target = T;
     fn deref(&self) -> &T {
-        &self.inner
+        self.column
+            .as_any()
+            .downcast_ref::<TypedStorage<T>>()
+            .and_then(|storage| storage.get(self.entity))
+            .expect(COMPONENT_PRESENT)
     }
 }
 
-impl<'a, T> Deref for ComponentReferMut<'a, T> {
+impl<'a, T: 'static> Deref for ComponentReferMut<'a, T> {
     type Target = T;
     fn deref(&self) -> &T {
-        &self.inner
+        self.column
+            .as_any()
+            .downcast_ref::<TypedStorage<T>>()
+            .and_then(|storage| storage.get(self.entity))
+            .expect(COMPONENT_PRESENT)
     }
 }
 
-impl<'a, T> DerefMut for ComponentReferMut<'a, T> {
+impl<'a, T: 'static> DerefMut for ComponentReferMut<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        &mut self.inner
+        self.column
+            .as_any_mut()
+            .downcast_mut::<TypedStorage<T>>()
+            .and_then(|storage| storage.get_mut(self.entity))
+            .expect(COMPONENT_PRESENT)
     }
 }
 
-impl<'a, T: Clone> ComponentRefer<'a, T> {
+impl<'a, T: Clone + 'static> ComponentRefer<'a, T> {
     pub fn cloned(&self) -> T {
-        self.inner.clone()
+        (**self).clone()
     }
 }
 
@@ -121,7 +89,7 @@ pub struct Entity {
     pub id: u64,
     world: Arc<RwLock<World>>,
     command_buffer: Arc<RwLock<CommandBuffer>>,
-    borrow_tracker: Arc<BorrowTracker>,
+    borrow_trackers: Arc<BorrowTrackers>,
 }
 
 impl Entity {
@@ -130,44 +98,69 @@ impl Entity {
         id: u64,
         world: Arc<RwLock<World>>,
         command_buffer: Arc<RwLock<CommandBuffer>>,
+    ) -> Self {
+        let borrow_trackers = world.read().borrow_trackers();
+        Self::from_parts(id, world, command_buffer, borrow_trackers)
+    }
+
+    /// Create from existing entity ID without touching the world lock
+    pub(crate) fn from_parts(
+        id: u64,
+        world: Arc<RwLock<World>>,
+        command_buffer: Arc<RwLock<CommandBuffer>>,
+        borrow_trackers: Arc<BorrowTrackers>,
     ) -> Self {
         Self {
             id,
             world,
             command_buffer,
-            borrow_tracker: Arc::new(BorrowTracker::new()),
+            borrow_trackers,
         }
     }
 
     /// Create new Entity
     pub fn new(world: Arc<RwLock<World>>, command_buffer: Arc<RwLock<CommandBuffer>>) -> Self {
-        let id = world.write().create_entity_id();
-        world.write().register_entity(id);
+        let (id, borrow_trackers) = {
+            let mut world = world.write();
+            let id = world.create_entity_id();
+            world.register_entity(id);
+            (id, world.borrow_trackers())
+        };
 
-        Self {
-            id,
-            world,
-            command_buffer,
-            borrow_tracker: Arc::new(BorrowTracker::new()),
-        }
+        Self::from_parts(id, world, command_buffer, borrow_trackers)
     }
 
     /// Add a component immediately - executes right away
     ///
     /// Usage:
     /// ```
+    /// # use ecs_hybrid::*;
+    /// # let scene = Scene::new();
+    /// # let entity = scene.instantiate();
     /// entity.add_component(Transform::new(0.0, 0.0, 0.0));
     /// // Component is immediately accessible
     /// ```
     pub fn add_component<T: Send + Sync + 'static>(&self, component: T) -> &Self {
-        self.world.write().add_component(self.id, component);
+        // Only lock the column if it exists - a new component type is a structural change
+        let result = self.read_world().add_component_locked(self.id, component);
+        if let Err(component) = result {
+            self.world.write().add_component(self.id, component);
+        }
         self
     }
 
+    /// Attach a script - its lifecycle callbacks are driven by `Scene::update_scripts`
+    pub fn add_behaviour<B: Behaviour>(&self, behaviour: B) -> &Self {
+        self.add_component(Script::new(behaviour))
+    }
+
     /// Add a component deferred - queued until apply_commands()
     ///
     /// Usage:
     /// ```
+    /// # use ecs_hybrid::*;
+    /// # let scene = Scene::new();
+    /// # let entity = scene.instantiate();
     /// entity.add_component_deferred(Transform::new(0.0, 0.0, 0.0));
     /// // Component NOT accessible yet
     /// scene.apply_commands();
@@ -195,43 +188,57 @@ impl Entity {
     // }
 
     pub fn get_component_raw<T: 'static>(&self) -> Option<ComponentRefer<'_, T>> {
-        // Acquire read borrow (panics if already borrowed mutably)
-        let borrow_guard = self.borrow_tracker.borrow_read();
+        // Acquire read borrow (panics if already borrowed mutably by any handle)
+        let borrow_guard = self.borrow_trackers.get::<T>(self.id).borrow_read();
 
-        let guard = self.world.read();
-        let mapped =
-            RwLockReadGuard::try_map(guard, |world| world.get_component::<T>(self.id)).ok()?;
+        let world = self.read_world();
+        let column = world.column::<T>()?.read_arc();
+        column
+            .as_any()
+            .downcast_ref::<TypedStorage<T>>()?
+            .get(self.id)?;
 
         Some(ComponentRefer {
-            inner: mapped,
+            column,
+            entity: self.id,
+            _world: world,
             _borrow_guard: borrow_guard,
+            _marker: PhantomData,
         })
     }
 
     pub fn get_component_raw_mut<T: 'static>(&self) -> Option<ComponentReferMut<'_, T>> {
-        // Acquire write borrow (panics if already borrowed)
-        let borrow_guard = self.borrow_tracker.borrow_write();
+        // Acquire write borrow (panics if already borrowed by any handle)
+        let borrow_guard = self.borrow_trackers.get::<T>(self.id).borrow_write();
 
-        let guard = self.world.write();
-        let mapped =
-            RwLockWriteGuard::try_map(guard, |world| world.get_component_mut::<T>(self.id)).ok()?;
+        let world = self.read_world();
+        // Only the column of T is write-locked
+        let column = world.column::<T>()?.write_arc();
+        column
+            .as_any()
+            .downcast_ref::<TypedStorage<T>>()?
+            .get(self.id)?;
 
         Some(ComponentReferMut {
-            inner: mapped,
+            column,
+            entity: self.id,
+            _world: world,
             _borrow_guard: borrow_guard,
+            _marker: PhantomData,
         })
     }
 
     pub fn with_component<T: 'static, R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
-        let world = self.world.read();
+        let world = self.read_world();
         let comp = world.get_component::<T>(self.id)?;
-        Some(f(comp))
+        Some(f(&comp))
     }
 
+    /// Mutate a component - only blocks other accesses to the same component type
     pub fn with_component_mut<T: 'static, R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
-        let mut world = self.world.write();
-        let comp = world.get_component_mut::<T>(self.id)?;
-        Some(f(comp))
+        let world = self.read_world();
+        let mut comp = world.write_component::<T>(self.id)?;
+        Some(f(&mut comp))
     }
 
     // pub fn get_component_raw<T: 'static>(&self) -> Option<&T> {
@@ -255,13 +262,19 @@ impl Entity {
     where
         F: FnOnce(&[T]) -> R,
     {
-        let world = self.world.read();
+        let world = self.read_world();
         world.with_components::<T, R, F>(self.id, f)
     }
 
     /// Remove a component immediately
     pub fn remove_component<T: 'static>(&self) {
-        self.world.write().remove_component::<T>(self.id);
+        self.read_world().remove_component_locked::<T>(self.id);
+    }
+
+    /// Rename this Entity - replaces its Name so `Scene::find_by_name` sees the new one
+    pub fn set_name(&self, name: impl Into<String>) -> &Self {
+        self.remove_component::<Name>();
+        self.add_component(Name::new(name))
     }
 
     /// Remove a component deferred - queued until apply_commands()
@@ -269,19 +282,176 @@ impl Entity {
         self.command_buffer.write().remove_component::<T>(self.id);
     }
 
-    /// Destroy this Entity immediately
+    /// Destroy this Entity and, like Unity, all of its children immediately
     pub fn destroy(&self) {
-        self.world.write().destroy_entity(self.id);
+        self.world.write().destroy_recursive(self.id);
     }
 
-    /// Destroy this Entity deferred - queued until apply_commands()
+    /// Destroy this Entity and its children deferred - queued until apply_commands()
     pub fn destroy_deferred(&self) {
-        self.command_buffer.write().destroy_entity(self.id);
+        self.command_buffer.write().destroy_recursive(self.id);
     }
 
     /// Check if component exists
     pub fn has_component<T: 'static>(&self) -> bool {
-        self.world.read().get_component::<T>(self.id).is_some()
+        self.read_world().get_component::<T>(self.id).is_some()
+    }
+
+    /// Unity-like SetActive - an inactive entity and all its children are skipped by
+    /// queries, systems, scripts and coroutines until activated again
+    pub fn set_active(&self, active: bool) {
+        self.world.write().set_active(self.id, active);
+    }
+
+    /// Own active flag, ignoring parents (Unity's activeSelf)
+    pub fn is_active_self(&self) -> bool {
+        self.read_world().is_active_self(self.id)
+    }
+
+    /// Active itself and through every parent (Unity's activeInHierarchy)
+    pub fn is_active(&self) -> bool {
+        self.read_world().is_active(self.id)
+    }
+
+    /// Parent this Entity to another, or detach it with `None`
+    /// Returns false if the parent is this Entity or one of its children
+    pub fn set_parent(&self, parent: Option<&Entity>) -> bool {
+        self.world.write().set_parent(self.id, parent.map(|p| p.id))
+    }
+
+    pub fn parent(&self) -> Option<Entity> {
+        let parent = self.read_world().parent(self.id)?;
+        Some(self.sibling_handle(parent))
+    }
+
+    pub fn children(&self) -> Vec<Entity> {
+        let children = self.read_world().children(self.id).to_vec();
+        children
+            .into_iter()
+            .map(|id| self.sibling_handle(id))
+            .collect()
+    }
+
+    /// Find a child by name or relative path (`"Arm/Weapon"`) - Unity-like Transform.Find
+    pub fn find_child(&self, path: &str) -> Option<Entity> {
+        let id = self.read_world().find_child(self.id, path)?;
+        Some(self.sibling_handle(id))
+    }
+
+    /// Component on this Entity or its first active descendant that has one enabled
+    pub fn get_component_in_children<T: 'static>(&self) -> Option<ComponentRef<T>> {
+        let id = self.read_world().find_in_children::<T>(self.id)?;
+        Some(ComponentRef::new(self.world.clone(), id))
+    }
+
+    /// Component on this Entity or its closest active ancestor that has one enabled
+    pub fn get_component_in_parent<T: 'static>(&self) -> Option<ComponentRef<T>> {
+        let id = self.read_world().find_in_parent::<T>(self.id)?;
+        Some(ComponentRef::new(self.world.clone(), id))
+    }
+
+    /// Create a copy of this Entity with all of its components - see `World::clone_entity`
+    pub fn duplicate(&self) -> Result<Entity, CloneError> {
+        let id = self.world.write().clone_entity(self.id)?;
+        Ok(self.sibling_handle(id))
+    }
+
+    /// Readable multi-line description of this Entity and its components - see `World::inspect`
+    pub fn inspect(&self) -> String {
+        self.read_world().inspect(self.id)
+    }
+
+    /// Read a component field by path, e.g. `entity.get_field("Health.current")`
+    pub fn get_field(&self, path: &str) -> Result<Value, ReflectError> {
+        self.read_world().get_field(self.id, path)
+    }
+
+    /// Write a component field by path, e.g. `entity.set_field("Health.current", 50.0)`
+    pub fn set_field(&self, path: &str, value: impl Into<Value>) -> Result<(), ReflectError> {
+        self.read_world().set_field(self.id, path, value)
+    }
+
+    /// Prefab asset this Entity was instantiated from (with `Scene::instantiate_linked`)
+    pub fn prefab(&self) -> Option<PrefabHandle> {
+        self.read_world().prefab_of(self.id)
+    }
+
+    /// Type names of the components this prefab instance overrides
+    pub fn prefab_overrides(&self) -> Vec<&'static str> {
+        self.read_world().prefab_overrides(self.id)
+    }
+
+    /// Reset overridden components to the prefab's values
+    pub fn revert_to_prefab(&self) {
+        self.world.write().revert_to_prefab(self.id);
+    }
+
+    /// Write this instance's overrides into its prefab - returns the type names applied
+    pub fn apply_overrides_to_prefab(&self) -> Vec<&'static str> {
+        self.world.write().apply_overrides_to_prefab(self.id)
+    }
+
+    /// Enable or disable a component - disabled components are skipped by queries
+    pub fn set_component_enabled<T: 'static>(&self, enabled: bool) {
+        if let Some(mut storage) = self.read_world().write_storage::<T>() {
+            storage.set_enabled(self.id, enabled);
+        }
+    }
+
+    /// Check if component exists and is enabled
+    pub fn is_component_enabled<T: 'static>(&self) -> bool {
+        self.read_world().is_component_enabled::<T>(self.id)
+    }
+
+    /// Handle to another entity of the same scene
+    fn sibling_handle(&self, id: u64) -> Entity {
+        Entity::from_parts(
+            id,
+            self.world.clone(),
+            self.command_buffer.clone(),
+            self.borrow_trackers.clone(),
+        )
+    }
+
+    /// Read-only view holding one world lock for any number of components
+    pub fn read(&self) -> EntityRef<'_> {
+        EntityRef::new(self.id, self.read_world())
+    }
+
+    /// Mutable view holding a shared world lock - disjoint components can be borrowed
+    /// mutably at the same time, each locking only its own column
+    pub fn write(&self) -> EntityMut<'_> {
+        EntityMut::new(self.id, self.read_world())
+    }
+
+    /// Access several components at once under a single shared world lock
+    ///
+    /// Usage:
+    /// ```
+    /// # use ecs_hybrid::*;
+    /// # let scene = Scene::new();
+    /// # let entity = scene.instantiate();
+    /// # entity.add_component(Transform::new(0.0, 0.0, 0.0)).add_component(Velocity::new(1.0, 0.0, 0.0));
+    /// let moved = entity.with::<(&mut Transform, &Velocity)>(|(t, v)| t.x += v.x);
+    /// assert!(moved);
+    /// ```
+    ///
+    /// Only the columns of the borrowed types are locked (write-locked for `&mut`), taken in
+    /// `TypeId` order so two `with` calls can't deadlock. Returns false without calling `f`
+    /// if any component is missing; panics if the same type is borrowed twice mutably.
+    pub fn with<Q: ComponentAccess>(&self, f: impl FnOnce(Q::Item<'_>)) -> bool {
+        let world = self.read_world();
+        let Some(mut guard) = Q::lock(&world, self.id) else {
+            return false;
+        };
+        f(Q::item(&mut guard));
+        true
+    }
+
+    /// Shared (non-structural) world lock - recursive so nested accesses on one thread
+    /// can't deadlock behind a queued structural writer
+    fn read_world(&self) -> RwLockReadGuard<'_, World> {
+        self.world.read_recursive()
     }
 }
 
@@ -324,8 +494,9 @@ impl<T: 'static> ComponentRef<T> {
 
     /// Access the component through a closure
     pub fn with<R, F: FnOnce(&T) -> R>(&self, f: F) -> Option<R> {
-        let world = self.world.read();
-        world.get_component::<T>(self.entity_id).map(f)
+        let world = self.world.read_recursive();
+        let comp = world.get_component::<T>(self.entity_id)?;
+        Some(f(&comp))
     }
 }
 
@@ -347,8 +518,9 @@ impl<T: 'static> ComponentRefMut<T> {
 
     /// Access the component mutably through a closure
     pub fn with<R, F: FnOnce(&mut T) -> R>(&mut self, f: F) -> Option<R> {
-        let mut world = self.world.write();
-        world.get_component_mut::<T>(self.entity_id).map(f)
+        let world = self.world.read_recursive();
+        let mut comp = world.write_component::<T>(self.entity_id)?;
+        Some(f(&mut comp))
     }
 }
 
@@ -356,24 +528,78 @@ impl<T: 'static> ComponentRefMut<T> {
 pub struct Scene {
     world: Arc<RwLock<World>>,
     command_buffer: Arc<RwLock<CommandBuffer>>,
+    borrow_trackers: Arc<BorrowTrackers>,
+    coroutines: Mutex<CoroutineScheduler>,
+    scripts: Mutex<ScriptScheduler>,
 }
 
 impl Scene {
     pub fn new() -> Self {
+        let mut world = World::new();
+        world.set_defer_scripted_destroy(true);
+        let borrow_trackers = world.borrow_trackers();
+        let command_buffer = CommandBuffer::new();
+
         Self {
-            world: Arc::new(RwLock::new(World::new())),
-            command_buffer: Arc::new(RwLock::new(CommandBuffer::new())),
+            world: Arc::new(RwLock::new(world)),
+            command_buffer: Arc::new(RwLock::new(command_buffer)),
+            borrow_trackers,
+            coroutines: Mutex::new(CoroutineScheduler::default()),
+            scripts: Mutex::new(ScriptScheduler::default()),
         }
     }
 
     /// Instantiate a new Entity - Unity-like API
     pub fn instantiate(&self) -> Entity {
-        Entity::new(self.world.clone(), self.command_buffer.clone())
+        let id = {
+            let mut world = self.world.write();
+            let id = world.create_entity_id();
+            world.register_entity(id);
+            id
+        };
+        self.get_entity(id)
+    }
+
+    /// Instantiate a prefab - Unity-like Instantiate(prefab), returns the root Entity
+    ///
+    /// Use `CommandBuffer::instantiate_prefab` to defer it to `apply_commands`.
+    pub fn instantiate_prefab(&self, prefab: &Prefab) -> Entity {
+        let id = self.world.write().instantiate_prefab(prefab);
+        self.get_entity(id)
+    }
+
+    /// Instantiate a prefab asset and keep the instance linked to it - see `edit_prefab`
+    pub fn instantiate_linked(&self, prefab: &PrefabHandle) -> Entity {
+        let id = self.world.write().instantiate_linked(prefab);
+        self.get_entity(id)
+    }
+
+    /// Edit a prefab asset - linked instances follow for every component they did not override
+    pub fn edit_prefab(&self, prefab: &PrefabHandle, edit: impl FnOnce(&mut Prefab)) {
+        self.world.write().edit_prefab(prefab, edit);
+    }
+
+    /// Save every entity with its components (except `Script`s) and hierarchy as text - see
+    /// SCENE_FORMAT.md
+    pub fn save_to_string(&self) -> Result<String, SceneError> {
+        self.world.read().save_to_string()
+    }
+
+    /// Load entities saved with `save_to_string` into this Scene - returns the saved id ->
+    /// new id map and the migrations that ran. Component types must be registered as
+    /// serializable under the same IDs
+    pub fn load_from_str(&self, text: &str) -> Result<LoadReport, SceneError> {
+        self.world.write().load_from_str(text)
     }
 
     /// Get Entity from entity ID
     pub fn get_entity(&self, id: u64) -> Entity {
-        Entity::from_id(id, self.world.clone(), self.command_buffer.clone())
+        Entity::from_parts(
+            id,
+            self.world.clone(),
+            self.command_buffer.clone(),
+            self.borrow_trackers.clone(),
+        )
     }
 
     /// Access the world directly for system execution
@@ -391,11 +617,121 @@ impl Scene {
         self.command_buffer.clone()
     }
 
+    /// Check whether the entity is alive
+    pub fn contains_entity(&self, id: u64) -> bool {
+        self.world.read_recursive().contains_entity(id)
+    }
+
+    /// First active Entity with this Name - Unity-like GameObject.Find
+    pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+        let id = self.world.read_recursive().find_by_name(name)?;
+        Some(self.get_entity(id))
+    }
+
+    /// Find an active Entity by hierarchical name path - `scene.find("Player/Arm/Weapon")`
+    pub fn find(&self, path: &str) -> Option<Entity> {
+        let id = self.world.read_recursive().find_path(path)?;
+        Some(self.get_entity(id))
+    }
+
+    /// Every active Entity with this tag - Unity-like GameObject.FindGameObjectsWithTag
+    pub fn find_with_tag(&self, tag: &str) -> Vec<Entity> {
+        let ids = self.world.read_recursive().find_with_tag(tag);
+        ids.into_iter().map(|id| self.get_entity(id)).collect()
+    }
+
+    /// Every active Entity on at least one layer of the mask
+    pub fn find_in_layers(&self, layers: LayerMask) -> Vec<Entity> {
+        let ids = self.world.read_recursive().find_in_layers(layers);
+        ids.into_iter().map(|id| self.get_entity(id)).collect()
+    }
+
+    /// Tag an Entity - an Entity can have several tags
+    pub fn add_tag(&self, entity: &Entity, tag: &str) {
+        self.world.write().add_tag(entity.id, tag);
+    }
+
+    pub fn remove_tag(&self, entity: &Entity, tag: &str) {
+        self.world.write().remove_tag(entity.id, tag);
+    }
+
+    pub fn has_tag(&self, entity: &Entity, tag: &str) -> bool {
+        self.world.read_recursive().has_tag(entity.id, tag)
+    }
+
+    /// Put an Entity on a set of layers - systems can skip it with `QueryFilter::in_layers`
+    pub fn set_layers(&self, entity: &Entity, layers: LayerMask) {
+        self.world.write().set_layers(entity.id, layers);
+    }
+
+    pub fn layers(&self, entity: &Entity) -> LayerMask {
+        self.world.read_recursive().layers(entity.id)
+    }
+
+    /// Every active Entity with an enabled `T` - Unity-like FindObjectsOfType
+    pub fn find_all_with<T: 'static>(&self) -> Vec<Entity> {
+        let ids = self.world.read_recursive().find_all_with::<T>();
+        ids.into_iter().map(|id| self.get_entity(id)).collect()
+    }
+
+    /// Check if an entity is active in the hierarchy
+    pub fn is_entity_active(&self, id: u64) -> bool {
+        self.world.read_recursive().is_active(id)
+    }
+
+    /// Start a coroutine owned by an entity - Unity-like StartCoroutine
+    ///
+    /// Its first step runs on the next `update_coroutines`; it is cancelled automatically
+    /// when the entity is destroyed.
+    pub fn start_coroutine<C: Coroutine>(&self, entity: &Entity, routine: C) -> CoroutineId {
+        self.coroutines.lock().start(entity.id, Box::new(routine))
+    }
+
+    /// Stop a running coroutine
+    pub fn stop_coroutine(&self, id: CoroutineId) {
+        self.coroutines.lock().stop(id);
+    }
+
+    /// Stop every coroutine owned by an entity
+    pub fn stop_all_coroutines(&self, entity: &Entity) {
+        self.coroutines.lock().stop_all(entity.id);
+    }
+
+    /// Number of running coroutines
+    pub fn coroutine_count(&self) -> usize {
+        self.coroutines.lock().len()
+    }
+
+    /// Advance all coroutines by one frame - called by `SystemExecutor::run`. `execute` only
+    /// sees the World, so when driving systems with it, call this once per frame yourself
+    pub fn update_coroutines(&self, delta_time: f32) {
+        coroutine::update_coroutines(self, delta_time);
+    }
+
+    pub(crate) fn coroutines(&self) -> &Mutex<CoroutineScheduler> {
+        &self.coroutines
+    }
+
+    /// Run every script for one frame (awake/start for new ones, update, on_destroy for
+    /// removed ones) - called by `SystemExecutor::run`
+    pub fn update_scripts(&self, delta_time: f32) {
+        behaviour::update_scripts(self, delta_time);
+    }
+
+    pub(crate) fn scripts(&self) -> &Mutex<ScriptScheduler> {
+        &self.scripts
+    }
+
     /// Apply all pending commands - called at frame boundaries
+    ///
+    /// Destroyed entities with scripts are removed once their scripts ran `on_destroy`.
     pub fn apply_commands(&self) {
-        let mut cmd_buffer = self.command_buffer.write();
-        let mut world = self.world.write();
-        cmd_buffer.execute(&mut world);
+        {
+            let mut cmd_buffer = self.command_buffer.write();
+            let mut world = self.world.write();
+            cmd_buffer.execute(&mut world);
+        }
+        behaviour::destroy_pending(self);
     }
 }
 
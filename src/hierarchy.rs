@@ -0,0 +1,120 @@
+/// Parent/child links and active state - Unity-like Transform hierarchy and SetActive
+use std::collections::{HashMap, HashSet};
+
+/// Entity hierarchy owned by the World
+///
+/// An entity is active in the hierarchy only if it and all of its ancestors are active.
+/// That derived state is cached in `inactive_in_hierarchy` and refreshed for the affected
+/// subtree whenever activity or parenting changes, so queries only do a set lookup.
+#[derive(Default, Clone)]
+pub(crate) struct Hierarchy {
+    parents: HashMap<u64, u64>,
+    children: HashMap<u64, Vec<u64>>,
+    inactive_self: HashSet<u64>,
+    inactive_in_hierarchy: HashSet<u64>,
+}
+
+impl Hierarchy {
+    pub(crate) fn parent(&self, entity: u64) -> Option<u64> {
+        self.parents.get(&entity).copied()
+    }
+
+    pub(crate) fn children(&self, entity: u64) -> &[u64] {
+        self.children.get(&entity).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Entity and all its descendants, parents before children
+    pub(crate) fn subtree(&self, entity: u64) -> Vec<u64> {
+        let mut result = vec![entity];
+        let mut i = 0;
+        while i < result.len() {
+            result.extend_from_slice(self.children(result[i]));
+            i += 1;
+        }
+        result
+    }
+
+    fn is_ancestor(&self, ancestor: u64, mut entity: u64) -> bool {
+        while let Some(parent) = self.parent(entity) {
+            if parent == ancestor {
+                return true;
+            }
+            entity = parent;
+        }
+        false
+    }
+
+    /// Re-parent an entity - returns false (and changes nothing) if it would create a cycle
+    pub(crate) fn set_parent(&mut self, child: u64, parent: Option<u64>) -> bool {
+        if let Some(parent) = parent {
+            if parent == child || self.is_ancestor(child, parent) {
+                return false;
+            }
+        }
+
+        self.detach(child);
+        if let Some(parent) = parent {
+            self.parents.insert(child, parent);
+            self.children.entry(parent).or_default().push(child);
+        }
+        self.refresh(child);
+        true
+    }
+
+    fn detach(&mut self, child: u64) {
+        if let Some(old_parent) = self.parents.remove(&child) {
+            if let Some(siblings) = self.children.get_mut(&old_parent) {
+                siblings.retain(|c| *c != child);
+                if siblings.is_empty() {
+                    self.children.remove(&old_parent);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn set_active(&mut self, entity: u64, active: bool) {
+        if active {
+            self.inactive_self.remove(&entity);
+        } else {
+            self.inactive_self.insert(entity);
+        }
+        self.refresh(entity);
+    }
+
+    pub(crate) fn is_active_self(&self, entity: u64) -> bool {
+        !self.inactive_self.contains(&entity)
+    }
+
+    /// Active itself and through every ancestor
+    pub(crate) fn is_active(&self, entity: u64) -> bool {
+        !self.inactive_in_hierarchy.contains(&entity)
+    }
+
+    /// True if no entity is inactive - lets queries skip per-entity lookups
+    pub(crate) fn all_active(&self) -> bool {
+        self.inactive_in_hierarchy.is_empty()
+    }
+
+    /// Recompute cached activity for an entity and its subtree
+    fn refresh(&mut self, entity: u64) {
+        for e in self.subtree(entity) {
+            let parent_active = self.parent(e).map_or(true, |p| self.is_active(p));
+            if parent_active && self.is_active_self(e) {
+                self.inactive_in_hierarchy.remove(&e);
+            } else {
+                self.inactive_in_hierarchy.insert(e);
+            }
+        }
+    }
+
+    /// Forget a destroyed entity - its children become roots
+    pub(crate) fn remove(&mut self, entity: u64) {
+        self.detach(entity);
+        for child in self.children.remove(&entity).unwrap_or_default() {
+            self.parents.remove(&child);
+            self.refresh(child);
+        }
+        self.inactive_self.remove(&entity);
+        self.inactive_in_hierarchy.remove(&entity);
+    }
+}
@@ -0,0 +1,98 @@
+/// Unity-like layers - one bitmask per entity in a dense, id-indexed column
+use std::ops::{BitAnd, BitOr, Not};
+
+/// Set of up to 64 layers - used both as an entity's layers and as a query filter
+///
+/// Every entity starts on layer 0 (`LayerMask::DEFAULT`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayerMask(pub u64);
+
+impl LayerMask {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u64::MAX);
+    pub const DEFAULT: Self = Self(1);
+
+    /// Mask with a single layer (0..64)
+    pub const fn layer(index: u32) -> Self {
+        assert!(index < 64, "Layer index must be below 64");
+        Self(1 << index)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// True if the masks share at least one layer
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl Default for LayerMask {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl BitOr for LayerMask {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for LayerMask {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl Not for LayerMask {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Layers of every entity, owned by the World
+///
+/// Stored as one `u64` per entity in a dense column indexed by entity id (ids are
+/// allocated sequentially) - no per-entity map entries.
+#[derive(Default, Clone)]
+pub(crate) struct EntityMasks {
+    layers: Vec<u64>,
+}
+
+impl EntityMasks {
+    pub(crate) fn layers(&self, entity: u64) -> LayerMask {
+        LayerMask(
+            self.layers
+                .get(entity as usize)
+                .copied()
+                .unwrap_or(LayerMask::DEFAULT.0),
+        )
+    }
+
+    pub(crate) fn set_layers(&mut self, entity: u64, layers: LayerMask) {
+        let index = entity as usize;
+        if index >= self.layers.len() {
+            self.layers.resize(index + 1, LayerMask::DEFAULT.0);
+        }
+        self.layers[index] = layers.0;
+    }
+
+    /// Give `dst` the layers of `src`
+    pub(crate) fn copy(&mut self, src: u64, dst: u64) {
+        self.set_layers(dst, self.layers(src));
+    }
+
+    /// Forget a destroyed entity
+    pub(crate) fn remove(&mut self, entity: u64) {
+        if let Some(layers) = self.layers.get_mut(entity as usize) {
+            *layers = LayerMask::DEFAULT.0;
+        }
+    }
+}
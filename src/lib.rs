@@ -1,16 +1,55 @@
 // Library exports for the hybrid ECS engine
 
+mod behaviour;
+mod bitset;
+mod borrow_tracker;
 mod command_buffer;
+mod coroutine;
 mod ecs_core;
+mod entity_map;
+mod entity_view;
 mod game_object;
+mod hierarchy;
+mod layers;
+mod lookup;
+mod migration;
+mod patch;
+mod prefab;
+mod reflect;
+mod registry;
+mod replication;
+mod scene_format;
+mod snapshot;
+mod state_hash;
 mod systems;
+mod world_snapshot;
 
+pub use behaviour::{Behaviour, Script};
 pub use command_buffer::CommandBuffer;
-pub use ecs_core::World;
+pub use coroutine::{Coroutine, CoroutineId, Yield};
+pub use ecs_core::{
+    CloneError, ComponentReadGuard, ComponentWriteGuard, EntityAllocator, Query, Query2, Query3,
+    QueryFilter, StorageReadGuard, StorageWriteGuard, World,
+};
+pub use entity_map::{EntityMap, MapEntities};
+pub use entity_view::{ComponentAccess, ComponentBorrow, EntityMut, EntityRef, ViewMut, ViewRef};
 pub use game_object::{ComponentRef, ComponentRefMut, Entity, RawComponentRef, Scene};
+pub use layers::LayerMask;
+pub use migration::{AppliedMigration, ComponentData, LoadReport, MigrationFn};
+pub use patch::{ChangeKind, ComponentChange, EntityChange, WorldPatch};
+pub use prefab::{Prefab, PrefabHandle};
+pub use reflect::{FieldInfo, Reflect, ReflectError, Value};
+pub use registry::{
+    CloneFn, ComponentInfo, ComponentRegistry, DebugFn, DefaultFn, DropFn, ReflectFn, ReflectMutFn,
+};
+pub use replication::{ChannelTransport, ReplicationClient, ReplicationServer, Transport};
+pub use scene_format::SceneError;
+pub use snapshot::SnapshotError;
+pub use state_hash::StateHash;
 pub use systems::{GameSystem, System, SystemExecutor};
+pub use world_snapshot::WorldSnapshot;
 // Re-export common components
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Transform {
     pub x: f32,
     pub y: f32,
@@ -23,7 +62,7 @@ impl Transform {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Velocity {
     pub x: f32,
     pub y: f32,
@@ -36,7 +75,7 @@ impl Velocity {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Health {
     pub current: f32,
     pub max: f32,
@@ -48,7 +87,7 @@ impl Health {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Name {
     pub value: String,
 }
@@ -59,6 +98,11 @@ impl Name {
     }
 }
 
+impl_reflect!(Transform { x, y, z });
+impl_reflect!(Velocity { x, y, z });
+impl_reflect!(Health { current, max });
+impl_reflect!(Name { value });
+
 // Movement system implementation
 pub struct MovementSystem;
 
@@ -0,0 +1,221 @@
+/// Name and tag indexes behind `Scene::find_by_name` / `Scene::find_with_tag`
+use crate::ecs_core::{ColumnVersion, TypedStorage};
+use crate::Name;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// Entities by Name value, kept sorted by id
+///
+/// Updated by the World whenever it adds or removes a Name component, so lookups never
+/// scan the entity list. Names changed in place through a write guard or a mutable query
+/// are caught by the Name column's write count: the World rebuilds the index before the
+/// next lookup when the column was written since the index was last in sync.
+#[derive(Default, Clone)]
+pub(crate) struct LookupIndex {
+    names: HashMap<String, Vec<u64>>,
+    /// Version of the Name column the index matches - `None` until it has one
+    synced: Option<ColumnVersion>,
+}
+
+impl LookupIndex {
+    /// Empty index matching the Name column at `version`
+    pub(crate) fn synced_at(version: Option<ColumnVersion>) -> Self {
+        Self {
+            names: HashMap::new(),
+            synced: version,
+        }
+    }
+
+    pub(crate) fn is_synced(&self, version: Option<ColumnVersion>) -> bool {
+        self.synced == version
+    }
+
+    pub(crate) fn mark_synced(&mut self, version: Option<ColumnVersion>) {
+        self.synced = version;
+    }
+
+    /// The index was updated under the Name column's write lock, whose acquisition counted
+    /// as the write leading to `version` - still in sync if it was right before that write
+    pub(crate) fn advance(&mut self, version: Option<ColumnVersion>) {
+        if let Some((column, writes)) = version {
+            if self.synced == Some((column, writes.wrapping_sub(1))) {
+                self.synced = version;
+            }
+        }
+    }
+
+    /// Index every Name of the column from scratch
+    pub(crate) fn rebuild(&mut self, names: &TypedStorage<Name>, version: Option<ColumnVersion>) {
+        self.names.clear();
+        for (entity, components) in names.iter_all() {
+            for name in components {
+                self.insert(entity, name);
+            }
+        }
+        self.synced = version;
+    }
+
+    /// Whether components of type `T` are indexed
+    pub(crate) fn is_indexed<T: 'static>() -> bool {
+        TypeId::of::<T>() == TypeId::of::<Name>()
+    }
+
+    /// Name a component is indexed under
+    fn key<T: 'static>(component: &T) -> Option<&str> {
+        (component as &dyn Any)
+            .downcast_ref::<Name>()
+            .map(|name| name.value.as_str())
+    }
+
+    pub(crate) fn insert<T: 'static>(&mut self, entity: u64, component: &T) {
+        if let Some(key) = Self::key(component) {
+            let entities = self.names.entry(key.to_string()).or_default();
+            let position = entities.partition_point(|e| *e < entity);
+            entities.insert(position, entity);
+        }
+    }
+
+    pub(crate) fn remove<T: 'static>(&mut self, entity: u64, components: &[T]) {
+        for key in components.iter().filter_map(Self::key) {
+            if let Some(entities) = self.names.get_mut(key) {
+                entities.retain(|e| *e != entity);
+                if entities.is_empty() {
+                    self.names.remove(key);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn with_name(&self, name: &str) -> &[u64] {
+        self.names.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Tags of every entity, owned by the World
+///
+/// Tag names are interned to bits the first time they are used, and an entity's tags are a
+/// row of `stride` words in a dense matrix indexed by entity id - a single `u64` for up to
+/// 64 distinct tags, with every row widened by one word each time another 64 are used.
+/// No per-entity map entries. Each tag also keeps its members sorted by id so
+/// `find_with_tag` does not scan every entity.
+#[derive(Default, Clone)]
+pub(crate) struct TagIndex {
+    masks: Vec<u64>,
+    stride: usize,
+    bits: HashMap<String, u32>,
+    tagged: Vec<Vec<u64>>,
+}
+
+impl TagIndex {
+    /// Bit of an already used tag
+    fn bit(&self, tag: &str) -> Option<u32> {
+        self.bits.get(tag).copied()
+    }
+
+    /// Bit of a tag, interning it on first use
+    fn intern(&mut self, tag: &str) -> u32 {
+        if let Some(bit) = self.bit(tag) {
+            return bit;
+        }
+        let bit = self.tagged.len() as u32;
+        if bit as usize / 64 == self.stride {
+            self.widen();
+        }
+        self.bits.insert(tag.to_string(), bit);
+        self.tagged.push(Vec::new());
+        bit
+    }
+
+    /// Add one word to every row
+    fn widen(&mut self) {
+        let stride = self.stride + 1;
+        let rows = self.masks.len().checked_div(self.stride).unwrap_or(0);
+        let mut masks = vec![0; rows * stride];
+        for (row, words) in self.masks.chunks(self.stride.max(1)).enumerate() {
+            masks[row * stride..row * stride + words.len()].copy_from_slice(words);
+        }
+        self.masks = masks;
+        self.stride = stride;
+    }
+
+    /// Word holding a bit of an entity's row, and the bit's mask within it
+    fn locate(&self, entity: u64, bit: u32) -> (usize, u64) {
+        (
+            entity as usize * self.stride + bit as usize / 64,
+            1 << (bit % 64),
+        )
+    }
+
+    fn is_set(&self, entity: u64, bit: u32) -> bool {
+        let (word, mask) = self.locate(entity, bit);
+        self.masks.get(word).is_some_and(|w| w & mask != 0)
+    }
+
+    pub(crate) fn add(&mut self, entity: u64, tag: &str) {
+        let bit = self.intern(tag);
+        let (word, mask) = self.locate(entity, bit);
+        if word >= self.masks.len() {
+            self.masks.resize((entity as usize + 1) * self.stride, 0);
+        }
+        if self.masks[word] & mask == 0 {
+            self.masks[word] |= mask;
+            let members = &mut self.tagged[bit as usize];
+            let position = members.partition_point(|e| *e < entity);
+            members.insert(position, entity);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, entity: u64, tag: &str) {
+        if let Some(bit) = self.bit(tag) {
+            self.clear_bit(entity, bit);
+        }
+    }
+
+    fn clear_bit(&mut self, entity: u64, bit: u32) {
+        if self.is_set(entity, bit) {
+            let (word, mask) = self.locate(entity, bit);
+            self.masks[word] &= !mask;
+            self.tagged[bit as usize].retain(|e| *e != entity);
+        }
+    }
+
+    pub(crate) fn has(&self, entity: u64, tag: &str) -> bool {
+        self.bit(tag).is_some_and(|bit| self.is_set(entity, bit))
+    }
+
+    /// Tags of an entity, in the order they were first used
+    pub(crate) fn of(&self, entity: u64) -> Vec<&str> {
+        let mut names: Vec<(&str, u32)> = self
+            .bits
+            .iter()
+            .filter(|(_, bit)| self.is_set(entity, **bit))
+            .map(|(tag, bit)| (tag.as_str(), *bit))
+            .collect();
+        names.sort_by_key(|(_, bit)| *bit);
+        names.into_iter().map(|(tag, _)| tag).collect()
+    }
+
+    /// Entities with a tag, sorted by id
+    pub(crate) fn tagged(&self, tag: &str) -> &[u64] {
+        self.bit(tag)
+            .map(|bit| self.tagged[bit as usize].as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Give `dst` the tags of `src`
+    pub(crate) fn copy(&mut self, src: u64, dst: u64) {
+        let tags: Vec<String> = self.of(src).into_iter().map(str::to_string).collect();
+        for tag in tags {
+            self.add(dst, &tag);
+        }
+    }
+
+    /// Forget a destroyed entity
+    pub(crate) fn remove_entity(&mut self, entity: u64) {
+        for bit in 0..self.tagged.len() as u32 {
+            self.clear_bit(entity, bit);
+        }
+    }
+}
@@ -106,6 +106,7 @@ fn main() {
             .query::<Transform>()
             .map(|query| {
                 query
+                    .iter()
                     .filter_map(|(entity, transform)| {
                         world
                             .get_component::<Name>(entity)
@@ -163,7 +164,7 @@ fn main() {
     let world = world_lock.read();
     let remaining: Vec<String> = world
         .query::<Name>()
-        .map(|query| query.map(|(_, name)| name.value.clone()).collect())
+        .map(|query| query.iter().map(|(_, name)| name.value.clone()).collect())
         .unwrap_or_default();
     drop(world);
     drop(world_lock);
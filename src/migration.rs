@@ -0,0 +1,181 @@
+/// Component schema versions and migrations - upgrade old save data while loading
+use crate::entity_map::EntityMap;
+use crate::reflect::{Reflect, ReflectError, Value};
+use crate::registry::ComponentInfo;
+use std::any::Any;
+use std::collections::BTreeMap;
+
+/// Upgrades the saved fields of a component by one schema version
+pub type MigrationFn = fn(&mut ComponentData);
+
+/// Saved fields of one component instance, by path - what migrations operate on
+///
+/// ```
+/// # use ecs_hybrid::*;
+/// // Health v1 stored `hp`, v2 renamed it to `current` and added `max`
+/// fn health_v1_to_v2(data: &mut ComponentData) {
+///     data.rename("hp", "current");
+///     let current = data.get("current").cloned().unwrap_or(Value::Float(0.0));
+///     data.set("max", current);
+/// }
+///
+/// let mut world = World::new();
+/// world.registry_mut().set_version::<Health>(2);
+/// world.registry_mut().add_migration::<Health>(1, health_v1_to_v2);
+///
+/// let report = world
+///     .load_from_str("ecs_hybrid scene 1\nentity 0\n  component Health\n    hp = 40.0\n")
+///     .unwrap();
+/// let entity = report.entities.map(0);
+/// assert_eq!(world.get_field(entity, "Health.max"), Ok(Value::Float(40.0)));
+/// assert_eq!(report.migrations[0].to, 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComponentData {
+    fields: Vec<(String, Value)>,
+}
+
+impl ComponentData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every leaf field of a component value
+    pub(crate) fn of(value: &dyn Reflect) -> Self {
+        let mut data = Self::new();
+        value.for_each_leaf(&mut |path, value| data.fields.push((path.to_string(), value)));
+        data
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == path)
+            .map(|(_, value)| value)
+    }
+
+    /// Set a field, adding it if it is not stored
+    pub fn set(&mut self, path: &str, value: impl Into<Value>) {
+        let value = value.into();
+        match self.fields.iter_mut().find(|(field, _)| field == path) {
+            Some((_, existing)) => *existing = value,
+            None => self.fields.push((path.to_string(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, path: &str) -> Option<Value> {
+        let index = self.fields.iter().position(|(field, _)| field == path)?;
+        Some(self.fields.remove(index).1)
+    }
+
+    /// Move a field to a new path - returns false if it is not stored
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        match self.remove(from) {
+            Some(value) => {
+                self.set(to, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.fields
+            .iter()
+            .map(|(field, value)| (field.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// One migration step that ran while loading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    /// Component ID
+    pub component: String,
+    pub from: u32,
+    pub to: u32,
+    /// Number of component instances upgraded
+    pub instances: usize,
+}
+
+/// Result of loading a scene or snapshot
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    /// Saved entity id -> id in the World (the identity for snapshots, which keep ids)
+    pub entities: EntityMap,
+    /// Migrations that ran, by component and version
+    pub migrations: Vec<AppliedMigration>,
+}
+
+/// Counts migration steps while a file is loaded
+#[derive(Default)]
+pub(crate) struct MigrationLog {
+    steps: BTreeMap<(String, u32), usize>,
+}
+
+impl MigrationLog {
+    pub(crate) fn into_migrations(self) -> Vec<AppliedMigration> {
+        self.steps
+            .into_iter()
+            .map(|((component, from), instances)| AppliedMigration {
+                component,
+                from,
+                to: from + 1,
+                instances,
+            })
+            .collect()
+    }
+}
+
+/// Why saved fields could not be turned into a component
+pub(crate) enum BuildError {
+    /// Schema version problem - no field involved
+    Version(String),
+    /// A field could not be written
+    Field(String, ReflectError),
+}
+
+/// Upgrade saved fields from `version` to the registered schema version, then write them
+/// into a default value of the component type
+pub(crate) fn build(
+    info: &ComponentInfo,
+    version: u32,
+    mut data: ComponentData,
+    log: &mut MigrationLog,
+) -> Result<Box<dyn Any + Send + Sync>, BuildError> {
+    if version > info.version() {
+        return Err(BuildError::Version(format!(
+            "saved with schema version {} but the registered version is {}",
+            version,
+            info.version()
+        )));
+    }
+    for from in version..info.version() {
+        let migrate = info.migration(from).ok_or_else(|| {
+            BuildError::Version(format!(
+                "no migration registered from schema version {} to {}",
+                from,
+                from + 1
+            ))
+        })?;
+        migrate(&mut data);
+        *log.steps.entry((info.id().to_string(), from)).or_default() += 1;
+    }
+
+    let mut value = (info.default_fn().unwrap())();
+    let target: &mut dyn Any = value.as_mut();
+    let reflect = info.reflect_mut(target).unwrap();
+    for (path, field) in data.fields {
+        reflect
+            .set(&path, field)
+            .map_err(|error| BuildError::Field(path, error))?;
+    }
+    Ok(value)
+}
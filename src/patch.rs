@@ -0,0 +1,522 @@
+/// World diffs - entity and component changes between two Worlds, as a patch
+///
+/// Patches use the snapshot framing (see `snapshot.rs`) with their own magic bytes:
+///
+/// ```text
+/// header   magic "ECSHPTCH" | format version u32 | reserved u32 | payload length u64 | checksum u64
+/// payload  next entity id u64
+///          spawned: count u32, ids u64 | despawned: count u32, ids u64
+///          entity states: count u32, entities as in snapshots
+///          component changes: count u32, per change:
+///              entity u64 | component ID (string) | kind u8 (0 added, 1 changed, 2 removed)
+///              enabled u8 | instance count u32, per instance:
+///                  field count u32, per field: path (string) | value kind u8 | value
+/// ```
+use crate::ecs_core::World;
+use crate::entity_map::EntityMap;
+use crate::layers::LayerMask;
+use crate::migration::{self, BuildError, ComponentData, MigrationLog};
+use crate::registry::ComponentInfo;
+use crate::snapshot::{self, Decoder, Encoder, Kind, SnapshotError};
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 8] = b"ECSHPTCH";
+
+/// Changes that turn one World into another - made by `World::diff`, applied with
+/// `World::apply_patch`
+///
+/// Everything is sorted by entity id (then component ID), so the `Debug` output of a
+/// patch is stable and can be compared against golden files.
+///
+/// ```
+/// # use ecs_hybrid::*;
+/// let mut before = World::new();
+/// let player = before.create_entity();
+/// before.add_component(player, Health::new(100.0));
+///
+/// let mut after = World::new();
+/// after.restore(&before.snapshot());
+/// after.write_component::<Health>(player).unwrap().current = 75.0;
+/// let enemy = after.create_entity();
+/// after.add_component(enemy, Name::new("Enemy"));
+///
+/// let patch = before.diff(&after);
+/// assert_eq!(patch.spawned, [enemy]);
+/// assert_eq!(patch.components.len(), 2);
+///
+/// let mut bytes = Vec::new();
+/// patch.write(&mut bytes).unwrap();
+/// before.apply_patch(&WorldPatch::read(&mut bytes.as_slice()).unwrap()).unwrap();
+/// assert_eq!(before.state_hash(), after.state_hash());
+/// assert!(before.diff(&after).is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorldPatch {
+    /// Entity id allocator of the target World
+    pub next_entity_id: u64,
+    pub spawned: Vec<u64>,
+    pub despawned: Vec<u64>,
+    /// Hierarchy, active state, layers and tags of spawned entities and of the entities
+    /// where they changed
+    pub entities: Vec<EntityChange>,
+    pub components: Vec<ComponentChange>,
+}
+
+impl WorldPatch {
+    /// No entity or component changed (the id allocator may have)
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty()
+            && self.despawned.is_empty()
+            && self.entities.is_empty()
+            && self.components.is_empty()
+    }
+
+    /// Write the patch in the binary snapshot format
+    pub fn write(&self, out: &mut impl Write) -> Result<(), SnapshotError> {
+        let mut payload = Encoder::default();
+        payload.u64(self.next_entity_id);
+        for ids in [&self.spawned, &self.despawned] {
+            payload.len(ids.len());
+            for &id in ids {
+                payload.u64(id);
+            }
+        }
+        payload.len(self.entities.len());
+        for change in &self.entities {
+            let tags: Vec<&str> = change.tags.iter().map(String::as_str).collect();
+            payload.entity(
+                change.entity,
+                change.parent,
+                change.active,
+                change.layers,
+                &tags,
+            );
+        }
+        payload.len(self.components.len());
+        for change in &self.components {
+            payload.u64(change.entity);
+            payload.str(&change.component);
+            payload.u8(change.kind as u8);
+            payload.u8(change.enabled as u8);
+            payload.len(change.instances.len());
+            for data in &change.instances {
+                payload.len(data.len());
+                for (path, value) in data.iter() {
+                    let kind = Kind::of("", value);
+                    payload.str(path);
+                    payload.u8(kind as u8);
+                    payload.value(kind, value);
+                }
+            }
+        }
+        snapshot::write_framed(MAGIC, &payload.bytes, out)
+    }
+
+    /// Read a patch written with `write` - its checksum is verified
+    pub fn read(input: &mut impl Read) -> Result<WorldPatch, SnapshotError> {
+        let (_, payload) = snapshot::read_framed(MAGIC, input)?;
+        let mut decoder = Decoder { bytes: &payload };
+        let mut patch = WorldPatch {
+            next_entity_id: decoder.u64()?,
+            ..Default::default()
+        };
+        for ids in [&mut patch.spawned, &mut patch.despawned] {
+            for _ in 0..decoder.len()? {
+                ids.push(decoder.u64()?);
+            }
+        }
+        for _ in 0..decoder.len()? {
+            let entity = decoder.entity()?;
+            patch.entities.push(EntityChange {
+                entity: entity.id,
+                parent: entity.parent,
+                active: entity.active,
+                layers: entity.layers,
+                tags: entity.tags,
+            });
+        }
+        for _ in 0..decoder.len()? {
+            let entity = decoder.u64()?;
+            let component = decoder.str()?;
+            let kind = decoder.u8()?;
+            let kind = ChangeKind::from_u8(kind)
+                .ok_or_else(|| SnapshotError::Corrupt(format!("invalid change kind {}", kind)))?;
+            let enabled = decoder.u8()? != 0;
+            let mut instances = Vec::new();
+            for _ in 0..decoder.len()? {
+                let mut data = ComponentData::new();
+                for _ in 0..decoder.len()? {
+                    let path = decoder.str()?;
+                    let kind = decoder.u8()?;
+                    let kind = Kind::from_u8(kind).ok_or_else(|| {
+                        SnapshotError::Corrupt(format!("invalid field kind {}", kind))
+                    })?;
+                    data.set(&path, decoder.value(kind)?);
+                }
+                instances.push(data);
+            }
+            patch.components.push(ComponentChange {
+                entity,
+                component,
+                kind,
+                enabled,
+                instances,
+            });
+        }
+        if !decoder.bytes.is_empty() {
+            return Err(SnapshotError::Corrupt("trailing data".to_string()));
+        }
+        Ok(patch)
+    }
+}
+
+/// New state of one entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityChange {
+    pub entity: u64,
+    pub parent: Option<u64>,
+    pub active: bool,
+    pub layers: LayerMask,
+    pub tags: Vec<String>,
+}
+
+impl EntityChange {
+    fn of(world: &World, entity: u64) -> Self {
+        Self {
+            entity,
+            parent: world.parent(entity),
+            active: world.is_active_self(entity),
+            layers: world.layers(entity),
+            tags: {
+                // Sorted - tag bits, and so `World::tags` order, differ between Worlds
+                let mut tags: Vec<String> =
+                    world.tags(entity).into_iter().map(str::to_string).collect();
+                tags.sort_unstable();
+                tags
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added = 0,
+    Changed = 1,
+    Removed = 2,
+}
+
+impl ChangeKind {
+    fn from_u8(kind: u8) -> Option<Self> {
+        [ChangeKind::Added, ChangeKind::Changed, ChangeKind::Removed]
+            .into_iter()
+            .find(|k| *k as u8 == kind)
+    }
+}
+
+/// New value of every instance of one component type on an entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentChange {
+    pub entity: u64,
+    /// Component ID
+    pub component: String,
+    pub kind: ChangeKind,
+    pub enabled: bool,
+    /// Fields of every instance - empty when removed
+    pub instances: Vec<ComponentData>,
+}
+
+/// Component value built from patch fields
+type BuiltValue = Box<dyn Any + Send + Sync>;
+
+/// Enabled flag and fields of every instance, by (entity, component ID)
+type Components = BTreeMap<(u64, String), (bool, Vec<ComponentData>)>;
+
+/// Entity states and component values of a World, captured for diffing
+#[derive(Default)]
+pub(crate) struct State {
+    next_entity_id: u64,
+    entities: BTreeMap<u64, EntityChange>,
+    components: Components,
+}
+
+impl State {
+    /// Capture the component types `include` accepts (they must be serializable) - and
+    /// every entity, or only the entities holding one of those types
+    pub(crate) fn capture(
+        world: &World,
+        include: impl Fn(&ComponentInfo) -> bool,
+        every_entity: bool,
+    ) -> Self {
+        let mut components = Components::new();
+        for info in world.registry().iter().filter(|info| include(info)) {
+            let Some(storage) = world.read_column(info.type_id()) else {
+                continue;
+            };
+            storage.for_each_entity(&mut |entity| {
+                let instances = (0..storage.count(entity))
+                    .map(|index| {
+                        let value = storage.get_dyn(entity, index).unwrap();
+                        ComponentData::of(info.reflect(value).unwrap())
+                    })
+                    .collect();
+                components.insert(
+                    (entity, info.id().to_string()),
+                    (storage.is_enabled(entity), instances),
+                );
+            });
+        }
+
+        let entities: BTreeSet<u64> = match every_entity {
+            true => world.entities().collect(),
+            false => components.keys().map(|(entity, _)| *entity).collect(),
+        };
+        let entities = entities
+            .iter()
+            .map(|&entity| {
+                let mut state = EntityChange::of(world, entity);
+                // Parents outside the captured entities cannot be referenced
+                state.parent = state.parent.filter(|parent| entities.contains(parent));
+                (entity, state)
+            })
+            .collect();
+        State {
+            next_entity_id: world.next_entity_id(),
+            entities,
+            components,
+        }
+    }
+
+    /// Changes from `self` to `to`
+    pub(crate) fn diff(&self, to: &State) -> WorldPatch {
+        let mut patch = WorldPatch {
+            next_entity_id: to.next_entity_id,
+            ..Default::default()
+        };
+        for (&entity, state) in &to.entities {
+            match self.entities.get(&entity) {
+                None => patch.spawned.push(entity),
+                Some(old) if old == state => continue,
+                Some(_) => {}
+            }
+            patch.entities.push(state.clone());
+        }
+        patch.despawned = self
+            .entities
+            .keys()
+            .filter(|entity| !to.entities.contains_key(entity))
+            .copied()
+            .collect();
+
+        let mut changes: BTreeMap<&(u64, String), ComponentChange> = BTreeMap::new();
+        for (key, (enabled, instances)) in &to.components {
+            let kind = match self.components.get(key) {
+                None => ChangeKind::Added,
+                Some((was_enabled, was)) if was_enabled != enabled || was != instances => {
+                    ChangeKind::Changed
+                }
+                Some(_) => continue,
+            };
+            changes.insert(
+                key,
+                ComponentChange {
+                    entity: key.0,
+                    component: key.1.clone(),
+                    kind,
+                    enabled: *enabled,
+                    instances: instances.clone(),
+                },
+            );
+        }
+        // Components of despawned entities go with them
+        for key in self.components.keys() {
+            if !to.components.contains_key(key) && to.entities.contains_key(&key.0) {
+                changes.insert(
+                    key,
+                    ComponentChange {
+                        entity: key.0,
+                        component: key.1.clone(),
+                        kind: ChangeKind::Removed,
+                        enabled: true,
+                        instances: Vec::new(),
+                    },
+                );
+            }
+        }
+        patch.components = changes.into_values().collect();
+        patch
+    }
+}
+
+pub(crate) fn diff(from: &World, to: &World) -> WorldPatch {
+    let capture = |world| State::capture(world, ComponentInfo::is_serializable, true);
+    capture(from).diff(&capture(to))
+}
+
+/// Check that the hierarchy the patch leads to has no cycle - `alive` bounds any chain
+fn reject_cycles(world: &World, patch: &WorldPatch, alive: usize) -> Result<(), SnapshotError> {
+    let changes: BTreeMap<u64, Option<u64>> = patch
+        .entities
+        .iter()
+        .map(|change| (change.entity, change.parent))
+        .collect();
+    let parent = |entity: u64| match changes.get(&entity) {
+        Some(parent) => *parent,
+        None => world.parent(entity),
+    };
+    for (&entity, &new_parent) in &changes {
+        let mut current = new_parent;
+        for _ in 0..=alive {
+            match current {
+                Some(id) if id == entity => break,
+                Some(id) => current = parent(id),
+                None => break,
+            }
+        }
+        if current.is_some() {
+            return Err(SnapshotError::Corrupt(format!(
+                "reparenting entity {} makes a parent cycle",
+                entity
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Apply a patch - `map` remaps the entity ids held by component values (see `MapEntities`)
+pub(crate) fn apply(
+    world: &mut World,
+    patch: &WorldPatch,
+    map: Option<&EntityMap>,
+) -> Result<(), SnapshotError> {
+    let built = prepare(world, patch)?;
+    commit(world, patch, built, map)
+}
+
+/// Component values of a validated patch, one entry per `ComponentChange`
+pub(crate) struct Built {
+    components: Vec<(ComponentInfo, Vec<BuiltValue>)>,
+}
+
+/// Validate a patch against a World and build its component values, without changing
+/// anything - what `commit` needs to apply it
+///
+/// Only the entity ids' relations matter here, so a patch with the same shape but other
+/// ids for its spawned entities can be committed with the result.
+pub(crate) fn prepare(world: &World, patch: &WorldPatch) -> Result<Built, SnapshotError> {
+    let mut alive: HashSet<u64> = world.entities().collect();
+    alive.extend(&patch.spawned);
+    let missing = patch
+        .despawned
+        .iter()
+        .chain(patch.entities.iter().map(|change| &change.entity))
+        .chain(
+            patch
+                .entities
+                .iter()
+                .filter_map(|change| change.parent.as_ref()),
+        )
+        .chain(patch.components.iter().map(|change| &change.entity))
+        .find(|entity| !alive.contains(entity));
+    if let Some(&entity) = missing {
+        return Err(SnapshotError::MissingEntity(entity));
+    }
+    reject_cycles(world, patch, alive.len())?;
+
+    let mut log = MigrationLog::default();
+    let mut components = Vec::new();
+    for change in &patch.components {
+        let info = world
+            .registry()
+            .get_by_id(&change.component)
+            .ok_or_else(|| SnapshotError::UnknownComponent(change.component.clone()))?;
+        if !info.is_serializable() {
+            return Err(SnapshotError::NotSerializable {
+                entity: change.entity,
+                component: change.component.clone(),
+            });
+        }
+        let mut values = Vec::new();
+        for data in &change.instances {
+            let value = migration::build(info, info.version(), data.clone(), &mut log).map_err(
+                |error| match error {
+                    BuildError::Version(message) => SnapshotError::Migration {
+                        entity: change.entity,
+                        component: change.component.clone(),
+                        message,
+                    },
+                    BuildError::Field(_, error) => SnapshotError::Field {
+                        entity: change.entity,
+                        component: change.component.clone(),
+                        error,
+                    },
+                },
+            )?;
+            values.push(value);
+        }
+        components.push((info.clone(), values));
+    }
+    Ok(Built { components })
+}
+
+/// Apply a patch checked by `prepare`
+pub(crate) fn commit(
+    world: &mut World,
+    patch: &WorldPatch,
+    built: Built,
+    map: Option<&EntityMap>,
+) -> Result<(), SnapshotError> {
+    for &entity in &patch.spawned {
+        world.register_entity(entity);
+    }
+    // Detach every reparented entity before attaching any, so swapping a parent and its
+    // child never passes through a cycle
+    let reparented: Vec<&EntityChange> = patch
+        .entities
+        .iter()
+        .filter(|change| world.parent(change.entity) != change.parent)
+        .collect();
+    for change in &reparented {
+        world.set_parent(change.entity, None);
+    }
+    for change in &reparented {
+        if !world.set_parent(change.entity, change.parent) {
+            return Err(SnapshotError::Corrupt(format!(
+                "cannot parent entity {} to {:?}",
+                change.entity, change.parent
+            )));
+        }
+    }
+    for change in &patch.entities {
+        let entity = change.entity;
+        world.set_active(entity, change.active);
+        world.set_layers(entity, change.layers);
+        let tags: Vec<String> = world.tags(entity).into_iter().map(str::to_string).collect();
+        for tag in tags.iter().filter(|tag| !change.tags.contains(tag)) {
+            world.remove_tag(entity, tag);
+        }
+        for tag in &change.tags {
+            world.add_tag(entity, tag);
+        }
+    }
+    for ((info, values), change) in built.components.into_iter().zip(&patch.components) {
+        world.remove_component_by_type_id(info.type_id(), change.entity);
+        for mut value in values {
+            if let (Some(map), Some(map_entities)) = (map, info.map_entities_fn()) {
+                map_entities(value.as_mut(), map);
+            }
+            (info.insert_fn().unwrap())(world, change.entity, value);
+        }
+        if !change.enabled {
+            if let Some(storage) = world.column_mut(info.type_id()) {
+                storage.set_enabled(change.entity, false);
+            }
+        }
+    }
+    for &entity in &patch.despawned {
+        world.destroy_entity(entity);
+    }
+    world.advance_next_entity_id(patch.next_entity_id);
+    Ok(())
+}
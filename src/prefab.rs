@@ -0,0 +1,773 @@
+/// Prefabs - reusable entity templates, Unity-like
+use crate::ecs_core::World;
+use crate::entity_map::{EntityMap, MapEntities};
+use crate::registry::{CloneFn, InsertFn, MapEntitiesFn};
+use parking_lot::RwLock;
+use std::any::{Any, TypeId};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Type-erased component value stored in a prefab
+trait PrefabComponent: Send + Sync {
+    fn clone_box(&self) -> Box<dyn PrefabComponent>;
+    fn component_type(&self) -> TypeId;
+    fn component_name(&self) -> &'static str;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Register the type's clone functions with the world - see `register_types`
+    fn register(&self, world: &mut World);
+    /// Add a copy of the values to an entity, remapping prefab-local entity ids
+    fn insert(&self, world: &mut World, entity: u64, map: &EntityMap);
+    /// Replace every instance of the component on an entity with a copy of the values,
+    /// keeping the component disabled if it was
+    fn replace(&self, world: &mut World, entity: u64, map: &EntityMap);
+    /// True if the entity's instances of the component no longer match the (remapped) values
+    fn differs(&self, world: &World, entity: u64, map: &EntityMap) -> bool;
+    /// Copy the entity's instances back into prefab values (`to_local` maps ids back)
+    fn capture(
+        &self,
+        world: &World,
+        entity: u64,
+        to_local: &EntityMap,
+    ) -> Option<Box<dyn PrefabComponent>>;
+}
+
+/// Every instance of one component type on a prefab entity, plus how to remap the entity
+/// ids they hold (a no-op for most types)
+struct StoredComponent<T> {
+    values: Vec<T>,
+    map_entities: fn(&mut T, &EntityMap),
+}
+
+impl<T: Clone + PartialEq + Send + Sync + 'static> StoredComponent<T> {
+    fn new(value: T, map_entities: fn(&mut T, &EntityMap)) -> Self {
+        Self {
+            values: vec![value],
+            map_entities,
+        }
+    }
+
+    fn mapped(&self, map: &EntityMap) -> Vec<T> {
+        let mut values = self.values.clone();
+        for value in &mut values {
+            (self.map_entities)(value, map);
+        }
+        values
+    }
+}
+
+impl<T: Clone + PartialEq + Send + Sync + 'static> PrefabComponent for StoredComponent<T> {
+    fn clone_box(&self) -> Box<dyn PrefabComponent> {
+        Box::new(StoredComponent {
+            values: self.values.clone(),
+            map_entities: self.map_entities,
+        })
+    }
+
+    fn component_type(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn component_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn register(&self, world: &mut World) {
+        world.register_clone::<T>();
+    }
+
+    fn insert(&self, world: &mut World, entity: u64, map: &EntityMap) {
+        for value in self.mapped(map) {
+            world.add_component(entity, value);
+        }
+    }
+
+    fn replace(&self, world: &mut World, entity: u64, map: &EntityMap) {
+        replace_keeping_state(world, TypeId::of::<T>(), entity, |world| {
+            self.insert(world, entity, map)
+        });
+    }
+
+    fn differs(&self, world: &World, entity: u64, map: &EntityMap) -> bool {
+        world
+            .with_components::<T, _, _>(entity, |current| current != self.mapped(map).as_slice())
+            .unwrap_or(true)
+    }
+
+    fn capture(
+        &self,
+        world: &World,
+        entity: u64,
+        to_local: &EntityMap,
+    ) -> Option<Box<dyn PrefabComponent>> {
+        let mut values = world.with_components::<T, _, _>(entity, <[T]>::to_vec)?;
+        for value in &mut values {
+            (self.map_entities)(value, to_local);
+        }
+        Some(Box::new(StoredComponent {
+            values,
+            map_entities: self.map_entities,
+        }))
+    }
+}
+
+/// Instances of a component type a prefab entity did not have, captured from a linked
+/// instance by `apply_overrides` through the registry's clone functions
+///
+/// The type is only known type-erased, so instances are compared by count, not by value.
+struct CapturedComponent {
+    type_id: TypeId,
+    type_name: &'static str,
+    clone: CloneFn,
+    insert: InsertFn,
+    map_entities: Option<MapEntitiesFn>,
+    values: Vec<Box<dyn Any + Send + Sync>>,
+}
+
+impl CapturedComponent {
+    /// Copy an entity's instances of a type - `None` if the type is not registered as cloneable
+    fn capture(world: &World, type_id: TypeId, entity: u64, to_local: &EntityMap) -> Option<Self> {
+        let info = world.registry().get_by_type_id(type_id)?;
+        let mut captured = Self {
+            type_id,
+            type_name: info.type_name(),
+            clone: info.clone_fn()?,
+            insert: info.insert_fn()?,
+            map_entities: info.map_entities_fn(),
+            values: Vec::new(),
+        };
+        let column = world.read_column(type_id)?;
+        let values: Vec<&dyn Any> = (0..column.count(entity))
+            .filter_map(|index| column.get_dyn(entity, index))
+            .collect();
+        captured.values = captured.mapped(values, to_local);
+        Some(captured)
+    }
+
+    fn mapped<'a>(
+        &self,
+        values: impl IntoIterator<Item = &'a dyn Any>,
+        map: &EntityMap,
+    ) -> Vec<Box<dyn Any + Send + Sync>> {
+        values
+            .into_iter()
+            .map(|value| {
+                let mut value = (self.clone)(value);
+                if let Some(map_entities) = self.map_entities {
+                    map_entities(value.as_mut(), map);
+                }
+                value
+            })
+            .collect()
+    }
+
+    fn values(&self) -> impl Iterator<Item = &dyn Any> {
+        self.values.iter().map(|value| value.as_ref() as &dyn Any)
+    }
+}
+
+impl PrefabComponent for CapturedComponent {
+    fn clone_box(&self) -> Box<dyn PrefabComponent> {
+        Box::new(CapturedComponent {
+            values: self.values().map(self.clone).collect(),
+            ..*self
+        })
+    }
+
+    fn component_type(&self) -> TypeId {
+        self.type_id
+    }
+
+    fn component_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn register(&self, _world: &mut World) {
+        // Captured types are registered as cloneable already
+    }
+
+    fn insert(&self, world: &mut World, entity: u64, map: &EntityMap) {
+        for value in self.mapped(self.values(), map) {
+            (self.insert)(world, entity, value);
+        }
+    }
+
+    fn replace(&self, world: &mut World, entity: u64, map: &EntityMap) {
+        replace_keeping_state(world, self.type_id, entity, |world| {
+            self.insert(world, entity, map)
+        });
+    }
+
+    fn differs(&self, world: &World, entity: u64, _map: &EntityMap) -> bool {
+        let count = world
+            .read_column(self.type_id)
+            .map_or(0, |column| column.count(entity));
+        count != self.values.len()
+    }
+
+    fn capture(
+        &self,
+        world: &World,
+        entity: u64,
+        to_local: &EntityMap,
+    ) -> Option<Box<dyn PrefabComponent>> {
+        let captured = CapturedComponent::capture(world, self.type_id, entity, to_local)?;
+        (!captured.values.is_empty()).then(|| Box::new(captured) as Box<dyn PrefabComponent>)
+    }
+}
+
+/// Replace an entity's components of one type through `insert`, disabling the new ones if the
+/// old ones were disabled
+fn replace_keeping_state(
+    world: &mut World,
+    type_id: TypeId,
+    entity: u64,
+    insert: impl FnOnce(&mut World),
+) {
+    let disabled = world
+        .read_column(type_id)
+        .is_some_and(|column| column.contains(entity) && !column.is_enabled(entity));
+    world.remove_component_by_type_id(type_id, entity);
+    insert(world);
+    if disabled {
+        if let Some(column) = world.column_mut(type_id) {
+            column.set_enabled(entity, false);
+        }
+    }
+}
+
+/// Register the clone functions of every component type not yet in `registered` - prefabs
+/// do this once per type before inserting, not once per inserted component
+fn register_types<'a>(
+    world: &mut World,
+    registered: &mut HashSet<TypeId>,
+    components: impl IntoIterator<Item = &'a Box<dyn PrefabComponent>>,
+) {
+    for component in components {
+        if registered.insert(component.component_type()) {
+            component.register(world);
+        }
+    }
+}
+
+struct PrefabNode {
+    parent: Option<usize>,
+    components: Vec<Box<dyn PrefabComponent>>,
+}
+
+impl PrefabNode {
+    /// Add a component, replacing every value of the same type (how variants override)
+    fn set(&mut self, component: Box<dyn PrefabComponent>) {
+        let type_id = component.component_type();
+        match self
+            .components
+            .iter_mut()
+            .find(|c| c.component_type() == type_id)
+        {
+            Some(existing) => *existing = component,
+            None => self.components.push(component),
+        }
+    }
+}
+
+impl Clone for PrefabNode {
+    fn clone(&self) -> Self {
+        Self {
+            parent: self.parent,
+            components: self.components.iter().map(|c| c.clone_box()).collect(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Template of an entity (and optionally its children) that can be instantiated many times
+///
+/// Entities inside a prefab are identified by local ids: `Prefab::ROOT` and the ids returned
+/// by `add_child`. Components added with `add_component_mapped` may store those local ids;
+/// they are remapped to the new entities on every instantiation.
+///
+/// A variant (`Prefab::variant`) starts from a base prefab and only records the components
+/// it overrides, so later edits of the base show through.
+///
+/// Usage:
+/// ```
+/// # use ecs_hybrid::*;
+/// # let scene = Scene::new();
+/// let mut enemy = Prefab::new()
+///     .with(Name::new("Enemy"))
+///     .with(Health::new(50.0));
+/// let weapon = enemy.add_child(Prefab::ROOT);
+/// enemy.add_component(weapon, Name::new("Sword"));
+///
+/// let first = scene.instantiate_prefab(&enemy);
+/// let second = scene.instantiate_prefab(&enemy);
+/// assert_ne!(first.id, second.id);
+/// assert!(second.find_child("Sword").is_some());
+///
+/// // EliteEnemy = Enemy but Health 300
+/// let enemy = PrefabHandle::new(enemy);
+/// let elite = PrefabHandle::new(Prefab::variant(&enemy).with(Health::new(300.0)));
+/// let boss = scene.instantiate_linked(&elite);
+/// assert_eq!(boss.with_component::<Health, _>(|h| h.max), Some(300.0));
+/// ```
+#[derive(Clone)]
+pub struct Prefab {
+    base: Option<PrefabHandle>,
+    nodes: Vec<PrefabNode>,
+}
+
+impl Prefab {
+    /// Local id of the prefab's root entity
+    pub const ROOT: u64 = 0;
+
+    pub fn new() -> Self {
+        Self {
+            base: None,
+            nodes: vec![PrefabNode {
+                parent: None,
+                components: Vec::new(),
+            }],
+        }
+    }
+
+    /// Prefab inheriting every entity and component of `base` - components added to the
+    /// variant override the base's component of the same type
+    pub fn variant(base: &PrefabHandle) -> Self {
+        let mut variant = Self::new();
+        variant.base = Some(base.clone());
+        variant
+    }
+
+    pub fn base(&self) -> Option<&PrefabHandle> {
+        self.base.as_ref()
+    }
+
+    /// Add a component to the root - builder style
+    pub fn with<T: Clone + PartialEq + Send + Sync + 'static>(mut self, component: T) -> Self {
+        self.add_component(Self::ROOT, component);
+        self
+    }
+
+    /// Add a child entity under a prefab entity - returns its local id
+    pub fn add_child(&mut self, parent: u64) -> u64 {
+        assert!(
+            self.base.is_none(),
+            "Prefab variants cannot add children - add them to the base prefab"
+        );
+        self.node(parent);
+        self.nodes.push(PrefabNode {
+            parent: Some(parent as usize),
+            components: Vec::new(),
+        });
+        (self.nodes.len() - 1) as u64
+    }
+
+    /// Add a component to a prefab entity - another instance if it already has a `T`,
+    /// except in variants, where it replaces the base's instances of `T`
+    pub fn add_component<T: Clone + PartialEq + Send + Sync + 'static>(
+        &mut self,
+        entity: u64,
+        component: T,
+    ) -> &mut Self {
+        self.push(entity, component, |_, _| {})
+    }
+
+    /// Set the component of type `T` on a prefab entity, replacing any value of that type
+    pub fn set_component<T: Clone + PartialEq + Send + Sync + 'static>(
+        &mut self,
+        entity: u64,
+        component: T,
+    ) -> &mut Self {
+        self.node(entity)
+            .set(Box::new(StoredComponent::new(component, |_, _| {})));
+        self
+    }
+
+    /// Add a component holding prefab-local entity ids, remapped on instantiation
+    pub fn add_component_mapped<T: Clone + PartialEq + MapEntities + Send + Sync + 'static>(
+        &mut self,
+        entity: u64,
+        component: T,
+    ) -> &mut Self {
+        self.push(entity, component, T::map_entities)
+    }
+
+    fn push<T: Clone + PartialEq + Send + Sync + 'static>(
+        &mut self,
+        entity: u64,
+        value: T,
+        map_entities: fn(&mut T, &EntityMap),
+    ) -> &mut Self {
+        let is_variant = self.base.is_some();
+        let node = self.node(entity);
+        if is_variant {
+            node.set(Box::new(StoredComponent::new(value, map_entities)));
+            return self;
+        }
+        // Another instance of a type the entity already has
+        let existing = node
+            .components
+            .iter_mut()
+            .find(|c| c.component_type() == TypeId::of::<T>());
+        match existing {
+            Some(existing) => {
+                if let Some(captured) = existing.as_any_mut().downcast_mut::<CapturedComponent>() {
+                    // Captured from an instance - keep its values, now with their real type
+                    let values = captured
+                        .values()
+                        .filter_map(|v| v.downcast_ref::<T>().cloned())
+                        .collect();
+                    *existing = Box::new(StoredComponent {
+                        values,
+                        map_entities,
+                    });
+                }
+                if let Some(stored) = existing.as_any_mut().downcast_mut::<StoredComponent<T>>() {
+                    stored.values.push(value);
+                }
+            }
+            None => node
+                .components
+                .push(Box::new(StoredComponent::new(value, map_entities))),
+        }
+        self
+    }
+
+    /// Number of entities one instantiation creates
+    pub fn len(&self) -> usize {
+        match &self.base {
+            Some(base) => base.read().len(),
+            None => self.nodes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check if a prefab entity has a component of type `T` (including inherited ones)
+    pub fn has_component<T: 'static>(&self, entity: u64) -> bool {
+        self.resolve()
+            .nodes
+            .get(entity as usize)
+            .is_some_and(|node| {
+                node.components
+                    .iter()
+                    .any(|c| c.component_type() == TypeId::of::<T>())
+            })
+    }
+
+    /// Remove a component of type `T` from a prefab entity (only the variant's own override)
+    pub fn remove_component<T: 'static>(&mut self, entity: u64) -> &mut Self {
+        self.node(entity)
+            .components
+            .retain(|c| c.component_type() != TypeId::of::<T>());
+        self
+    }
+
+    fn node(&mut self, entity: u64) -> &mut PrefabNode {
+        let count = self.len();
+        assert!(
+            (entity as usize) < count,
+            "Prefab has no entity {} (it has {} entities)",
+            entity,
+            count
+        );
+        // Variants only store the entities they override
+        while self.nodes.len() <= entity as usize {
+            self.nodes.push(PrefabNode {
+                parent: None,
+                components: Vec::new(),
+            });
+        }
+        &mut self.nodes[entity as usize]
+    }
+
+    /// Flatten the inheritance chain into a plain prefab
+    fn resolve(&self) -> Prefab {
+        let Some(base) = &self.base else {
+            return self.clone();
+        };
+        let mut resolved = base.read().resolve();
+        for (node, overrides) in resolved.nodes.iter_mut().zip(&self.nodes) {
+            for component in &overrides.components {
+                node.set(component.clone_box());
+            }
+        }
+        resolved
+    }
+
+    /// Create the prefab's entities in a world - returns the local -> new id map
+    fn spawn(&self, world: &mut World) -> EntityMap {
+        let mut map = EntityMap::new();
+        let ids: Vec<u64> = (0..self.nodes.len())
+            .map(|local| {
+                let id = world.create_entity();
+                map.insert(local as u64, id);
+                id
+            })
+            .collect();
+
+        let components = self.nodes.iter().flat_map(|node| &node.components);
+        register_types(world, &mut HashSet::new(), components);
+        for (node, &id) in self.nodes.iter().zip(&ids) {
+            if let Some(parent) = node.parent {
+                world.set_parent(id, Some(ids[parent]));
+            }
+            for component in &node.components {
+                component.insert(world, id, &map);
+            }
+        }
+        map
+    }
+}
+
+impl Default for Prefab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Shared prefab asset - instances created with `instantiate_linked` stay connected to it
+///
+/// Edit it through `World::edit_prefab` (or `Scene::edit_prefab`) so linked instances that did
+/// not override a component pick up the change.
+#[derive(Clone)]
+pub struct PrefabHandle(Arc<RwLock<Prefab>>);
+
+impl PrefabHandle {
+    pub fn new(prefab: Prefab) -> Self {
+        Self(Arc::new(RwLock::new(prefab)))
+    }
+
+    /// Read the prefab
+    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, Prefab> {
+        self.0.read()
+    }
+
+    /// True if this is `other` or a variant of it (directly or through a chain)
+    pub fn inherits_from(&self, other: &PrefabHandle) -> bool {
+        let mut current = Some(self.clone());
+        while let Some(handle) = current {
+            if Arc::ptr_eq(&handle.0, &other.0) {
+                return true;
+            }
+            current = handle.read().base.clone();
+        }
+        false
+    }
+
+    fn edit(&self, edit: impl FnOnce(&mut Prefab)) {
+        edit(&mut self.0.write());
+    }
+}
+
+/// Connection of an instantiated entity to the prefab entity it came from
+#[derive(Clone)]
+pub(crate) struct PrefabLink {
+    prefab: PrefabHandle,
+    node: u64,
+    /// Prefab-local id -> entity id for the whole instance
+    map: Arc<EntityMap>,
+}
+
+impl PrefabLink {
+    pub(crate) fn prefab(&self) -> &PrefabHandle {
+        &self.prefab
+    }
+}
+
+/// Instantiate a prefab without keeping a link - returns the root entity
+pub(crate) fn instantiate(world: &mut World, prefab: &Prefab) -> u64 {
+    prefab.resolve().spawn(world).map(Prefab::ROOT)
+}
+
+/// Instantiate a prefab asset, linking every created entity to it - returns the root entity
+pub(crate) fn instantiate_linked(world: &mut World, handle: &PrefabHandle) -> u64 {
+    let map = Arc::new(handle.read().resolve().spawn(world));
+    for (node, entity) in map.iter() {
+        world.set_prefab_link(
+            entity,
+            PrefabLink {
+                prefab: handle.clone(),
+                node,
+                map: map.clone(),
+            },
+        );
+    }
+    map.map(Prefab::ROOT)
+}
+
+/// Type and type name of an overridden component
+type Override = (TypeId, &'static str);
+
+/// Components of a linked entity that its prefab entity does not have
+fn added(world: &World, node: &PrefabNode, entity: u64) -> Vec<Override> {
+    world
+        .components_of(entity)
+        .into_iter()
+        .map(|(info, _)| (info.type_id(), info.type_name()))
+        .filter(|(type_id, _)| {
+            !node
+                .components
+                .iter()
+                .any(|c| c.component_type() == *type_id)
+        })
+        .collect()
+}
+
+/// Components of a linked entity that differ from its prefab, were removed from it, or
+/// were added to it
+fn overridden(world: &World, link: &PrefabLink, entity: u64) -> Vec<Override> {
+    let resolved = link.prefab.read().resolve();
+    let Some(node) = resolved.nodes.get(link.node as usize) else {
+        return Vec::new();
+    };
+    let mut overridden: Vec<Override> = node
+        .components
+        .iter()
+        .filter(|c| c.differs(world, entity, &link.map))
+        .map(|c| (c.component_type(), c.component_name()))
+        .collect();
+    overridden.extend(added(world, node, entity));
+    overridden
+}
+
+/// Type names of the components of a linked entity that differ from its prefab
+pub(crate) fn overrides(world: &World, link: &PrefabLink, entity: u64) -> Vec<&'static str> {
+    overridden(world, link, entity)
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Reset every prefab component of a linked entity to the prefab's value and remove the
+/// components the prefab does not have
+pub(crate) fn revert(world: &mut World, link: &PrefabLink, entity: u64) {
+    let resolved = link.prefab.read().resolve();
+    if let Some(node) = resolved.nodes.get(link.node as usize) {
+        for (type_id, _) in added(world, node, entity) {
+            world.remove_component_by_type_id(type_id, entity);
+        }
+        register_types(world, &mut HashSet::new(), &node.components);
+        for component in &node.components {
+            component.replace(world, entity, &link.map);
+        }
+    }
+}
+
+/// Copy the overrides of a linked entity into its prefab, then refresh the other instances -
+/// returns the type names that were applied
+///
+/// Added components are applied if their type is registered as cloneable. Removed components
+/// are applied to plain prefabs only - a variant cannot drop what it inherits from its base.
+pub(crate) fn apply_overrides(
+    world: &mut World,
+    link: &PrefabLink,
+    entity: u64,
+) -> Vec<&'static str> {
+    let resolved = link.prefab.read().resolve();
+    let Some(node) = resolved.nodes.get(link.node as usize) else {
+        return Vec::new();
+    };
+    let is_variant = link.prefab.read().base().is_some();
+    let to_local = link
+        .map
+        .iter()
+        .fold(EntityMap::new(), |mut map, (local, id)| {
+            map.insert(id, local);
+            map
+        });
+
+    let mut applied = Vec::new();
+    let mut captured = Vec::new();
+    let mut removed = Vec::new();
+    for component in &node.components {
+        if !component.differs(world, entity, &link.map) {
+            continue;
+        }
+        match component.capture(world, entity, &to_local) {
+            Some(value) => captured.push(value),
+            None if is_variant => continue,
+            None => removed.push(component.component_type()),
+        }
+        applied.push(component.component_name());
+    }
+    for (type_id, name) in added(world, node, entity) {
+        if let Some(value) = CapturedComponent::capture(world, type_id, entity, &to_local) {
+            applied.push(name);
+            captured.push(Box::new(value));
+        }
+    }
+    if !applied.is_empty() {
+        edit(world, &link.prefab, |prefab| {
+            let node = prefab.node(link.node);
+            node.components
+                .retain(|c| !removed.contains(&c.component_type()));
+            for value in captured {
+                node.set(value);
+            }
+        });
+    }
+    applied
+}
+
+/// Edit a prefab asset and refresh the components of its linked instances (including
+/// instances of its variants) that were not overridden before the edit
+pub(crate) fn edit(world: &mut World, handle: &PrefabHandle, edit: impl FnOnce(&mut Prefab)) {
+    // Record what every affected instance overrides against the prefab as it was, and which
+    // types the prefab had
+    let affected: Vec<(u64, PrefabLink, Vec<Override>, Vec<TypeId>)> = world
+        .prefab_links()
+        .filter(|(_, link)| link.prefab.inherits_from(handle))
+        .map(|(entity, link)| {
+            let before = link
+                .prefab
+                .read()
+                .resolve()
+                .nodes
+                .get(link.node as usize)
+                .map(|node| node.components.iter().map(|c| c.component_type()).collect())
+                .unwrap_or_default();
+            let overridden = overridden(world, link, entity);
+            (entity, link.clone(), overridden, before)
+        })
+        .collect();
+
+    handle.edit(edit);
+
+    let mut registered = HashSet::new();
+    for (entity, link, overridden, before) in affected {
+        let is_overridden = |type_id: TypeId| overridden.iter().any(|(t, _)| *t == type_id);
+        let resolved = link.prefab.read().resolve();
+        let Some(node) = resolved.nodes.get(link.node as usize) else {
+            continue;
+        };
+        // Types the edit removed from the prefab
+        for &type_id in &before {
+            let kept = node
+                .components
+                .iter()
+                .any(|c| c.component_type() == type_id);
+            if !kept && !is_overridden(type_id) {
+                world.remove_component_by_type_id(type_id, entity);
+            }
+        }
+        register_types(world, &mut registered, &node.components);
+        for component in &node.components {
+            if !is_overridden(component.component_type()) {
+                component.replace(world, entity, &link.map);
+            }
+        }
+    }
+}
@@ -0,0 +1,369 @@
+/// Runtime reflection - read and write component fields by name, without compile-time types
+use std::any::Any;
+use std::fmt;
+
+/// Leaf value read from or written to a reflected field
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::String(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Float(value as f64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+/// Name and type of a reflected field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub type_name: &'static str,
+}
+
+/// Why a reflected read or write failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReflectError {
+    /// No component type is registered under this ID
+    UnknownComponent(String),
+    /// The component type is registered but not reflectable
+    NotReflected(String),
+    /// The entity has no such component (or not that many instances)
+    MissingComponent { entity: u64, component: String },
+    /// A path segment names no field
+    UnknownField(String),
+    /// The path ends on a struct instead of a leaf value
+    NotAValue(String),
+    /// The value cannot be stored in the field
+    TypeMismatch {
+        path: String,
+        expected: &'static str,
+        found: Value,
+    },
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectError::UnknownComponent(id) => write!(f, "unknown component '{}'", id),
+            ReflectError::NotReflected(id) => write!(f, "component '{}' is not reflectable", id),
+            ReflectError::MissingComponent { entity, component } => {
+                write!(f, "entity {} has no component '{}'", entity, component)
+            }
+            ReflectError::UnknownField(path) => write!(f, "no field '{}'", path),
+            ReflectError::NotAValue(path) => write!(f, "'{}' is not a value field", path),
+            ReflectError::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => write!(f, "cannot store {} in '{}' ({})", found, path, expected),
+        }
+    }
+}
+
+impl std::error::Error for ReflectError {}
+
+/// Type whose fields can be listed, read and written at runtime
+///
+/// Implement it for components with `impl_reflect!`; leaf types (numbers, `bool`,
+/// `String`) already implement it. Register reflectable components with
+/// `ComponentRegistry::register_reflect` to reach them through `World::get_field` and
+/// `World::set_field` with paths like `"Health.current"`.
+pub trait Reflect: Any + Send + Sync {
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Fields of a struct - empty for leaf values
+    fn fields(&self) -> Vec<FieldInfo> {
+        Vec::new()
+    }
+
+    fn field(&self, _name: &str) -> Option<&dyn Reflect> {
+        None
+    }
+
+    fn field_mut(&mut self, _name: &str) -> Option<&mut dyn Reflect> {
+        None
+    }
+
+    /// Value of a leaf - `None` for structs
+    fn value(&self) -> Option<Value> {
+        None
+    }
+
+    /// Store a value in a leaf - returns false if it does not fit
+    fn set_value(&mut self, _value: Value) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<'a> dyn Reflect + 'a {
+    /// Nested field by dot-separated path (`"position.x"`) - `""` is the value itself
+    pub fn path(&self, path: &str) -> Option<&dyn Reflect> {
+        path.split('.')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(self, |value, segment| value.field(segment))
+    }
+
+    pub fn path_mut(&mut self, path: &str) -> Option<&mut dyn Reflect> {
+        path.split('.')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(self, |value, segment| value.field_mut(segment))
+    }
+
+    /// Read the leaf value at a path
+    pub fn get(&self, path: &str) -> Result<Value, ReflectError> {
+        self.path(path)
+            .ok_or_else(|| ReflectError::UnknownField(path.to_string()))?
+            .value()
+            .ok_or_else(|| ReflectError::NotAValue(path.to_string()))
+    }
+
+    /// Write the leaf value at a path
+    pub fn set(&mut self, path: &str, value: Value) -> Result<(), ReflectError> {
+        let field = self
+            .path_mut(path)
+            .ok_or_else(|| ReflectError::UnknownField(path.to_string()))?;
+        if field.value().is_none() {
+            return Err(ReflectError::NotAValue(path.to_string()));
+        }
+        let expected = field.type_name();
+        if field.set_value(value.clone()) {
+            Ok(())
+        } else {
+            Err(ReflectError::TypeMismatch {
+                path: path.to_string(),
+                expected,
+                found: value,
+            })
+        }
+    }
+
+    /// Visit every leaf value with its path, depth first in field order
+    pub fn for_each_leaf(&self, f: &mut dyn FnMut(&str, Value)) {
+        self.visit_leaves(String::new(), f);
+    }
+
+    fn visit_leaves(&self, path: String, f: &mut dyn FnMut(&str, Value)) {
+        if let Some(value) = self.value() {
+            f(&path, value);
+            return;
+        }
+        for field in self.fields() {
+            let field_path = if path.is_empty() {
+                field.name.to_string()
+            } else {
+                format!("{}.{}", path, field.name)
+            };
+            if let Some(value) = self.field(field.name) {
+                value.visit_leaves(field_path, f);
+            }
+        }
+    }
+
+    pub fn downcast_ref<T: Reflect>(&self) -> Option<&T> {
+        self.as_any().downcast_ref::<T>()
+    }
+}
+
+macro_rules! reflect_int {
+    ($($type:ty),*) => {$(
+        impl Reflect for $type {
+            fn value(&self) -> Option<Value> {
+                // u64 values above i64::MAX (e.g. ids) wrap and are read back bit for bit
+                Some(Value::Int(*self as i64))
+            }
+
+            fn set_value(&mut self, value: Value) -> bool {
+                let Value::Int(value) = value else {
+                    return false;
+                };
+                match <$type>::try_from(value) {
+                    Ok(value) => *self = value,
+                    Err(_) if std::mem::size_of::<$type>() == 8 => *self = value as $type,
+                    Err(_) => return false,
+                }
+                true
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+        }
+    )*};
+}
+
+reflect_int!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+macro_rules! reflect_float {
+    ($($type:ty),*) => {$(
+        impl Reflect for $type {
+            fn value(&self) -> Option<Value> {
+                // Through the shortest decimal form, so 0.1f32 reads as 0.1 rather than 0.100000001
+                self.to_string().parse().ok().map(Value::Float)
+            }
+
+            fn set_value(&mut self, value: Value) -> bool {
+                match value {
+                    Value::Float(value) => *self = value as $type,
+                    Value::Int(value) => *self = value as $type,
+                    _ => return false,
+                }
+                true
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+        }
+    )*};
+}
+
+reflect_float!(f32, f64);
+
+impl Reflect for bool {
+    fn value(&self) -> Option<Value> {
+        Some(Value::Bool(*self))
+    }
+
+    fn set_value(&mut self, value: Value) -> bool {
+        match value {
+            Value::Bool(value) => {
+                *self = value;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Reflect for String {
+    fn value(&self) -> Option<Value> {
+        Some(Value::String(self.clone()))
+    }
+
+    fn set_value(&mut self, value: Value) -> bool {
+        match value {
+            Value::String(value) => {
+                *self = value;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Implement `Reflect` for a struct from the list of its reflected fields
+///
+/// Every listed field must itself implement `Reflect`; unlisted fields are invisible.
+///
+/// ```
+/// # use ecs_hybrid::*;
+/// #[derive(Debug, Clone, Default)]
+/// struct Stats {
+///     speed: f32,
+///     level: u32,
+/// }
+/// impl_reflect!(Stats { speed, level });
+///
+/// let mut world = World::new();
+/// world.registry_mut().set_id::<Stats>("Stats");
+/// world.registry_mut().register_reflect::<Stats>();
+///
+/// let entity = world.create_entity();
+/// world.add_component(entity, Stats::default());
+/// world.set_field(entity, "Stats.level", Value::Int(3)).unwrap();
+/// assert_eq!(world.get_field(entity, "Stats.level"), Ok(Value::Int(3)));
+/// ```
+#[macro_export]
+macro_rules! impl_reflect {
+    ($type:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::Reflect for $type {
+            fn fields(&self) -> Vec<$crate::FieldInfo> {
+                vec![$($crate::FieldInfo {
+                    name: stringify!($field),
+                    type_name: $crate::Reflect::type_name(&self.$field),
+                }),*]
+            }
+
+            fn field(&self, name: &str) -> Option<&dyn $crate::Reflect> {
+                match name {
+                    $(stringify!($field) => Some(&self.$field),)*
+                    _ => None,
+                }
+            }
+
+            fn field_mut(&mut self, name: &str) -> Option<&mut dyn $crate::Reflect> {
+                match name {
+                    $(stringify!($field) => Some(&mut self.$field),)*
+                    _ => None,
+                }
+            }
+
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+    };
+}
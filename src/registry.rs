@@ -0,0 +1,448 @@
+/// Component type registry - names and metadata of every component type a World knows
+use crate::ecs_core::{ComponentStorage, TypedStorage, World};
+use crate::entity_map::{EntityMap, MapEntities};
+use crate::migration::MigrationFn;
+use crate::reflect::Reflect;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Copy a component value (the `&dyn Any` must hold the registered type)
+pub type CloneFn = fn(&dyn Any) -> Box<dyn Any + Send + Sync>;
+/// Drop a component value in place (`std::ptr::drop_in_place` for the registered type)
+pub type DropFn = unsafe fn(*mut u8);
+/// Format a component value with its `Debug` implementation
+pub type DebugFn = fn(&dyn Any, &mut fmt::Formatter<'_>) -> fmt::Result;
+/// View a component value as `Reflect`
+pub type ReflectFn = fn(&dyn Any) -> &dyn Reflect;
+pub type ReflectMutFn = fn(&mut dyn Any) -> &mut dyn Reflect;
+
+/// Create a default value of a component type
+pub type DefaultFn = fn() -> Box<dyn Any + Send + Sync>;
+
+/// Adds a boxed component value (made by the type's `DefaultFn`) to an entity
+pub(crate) type InsertFn = fn(&mut World, u64, Box<dyn Any + Send + Sync>);
+/// Remaps the entity ids held by a component value
+pub(crate) type MapEntitiesFn = fn(&mut dyn Any, &EntityMap);
+
+/// Copies the components of one entity onto another within a column
+pub(crate) type CloneComponentsFn = fn(&mut dyn ComponentStorage, u64, u64);
+/// Copies a whole column
+pub(crate) type CloneStorageFn = fn(&dyn ComponentStorage) -> Box<dyn ComponentStorage>;
+
+fn clone_value<T: Clone + Send + Sync + 'static>(value: &dyn Any) -> Box<dyn Any + Send + Sync> {
+    Box::new(value.downcast_ref::<T>().unwrap().clone())
+}
+
+unsafe fn drop_value<T>(value: *mut u8) {
+    std::ptr::drop_in_place(value.cast::<T>());
+}
+
+fn debug_value<T: fmt::Debug + 'static>(
+    value: &dyn Any,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    fmt::Debug::fmt(value.downcast_ref::<T>().unwrap(), f)
+}
+
+fn reflect_value<T: Reflect>(value: &dyn Any) -> &dyn Reflect {
+    value.downcast_ref::<T>().unwrap()
+}
+
+fn reflect_value_mut<T: Reflect>(value: &mut dyn Any) -> &mut dyn Reflect {
+    value.downcast_mut::<T>().unwrap()
+}
+
+fn default_value<T: Default + Send + Sync + 'static>() -> Box<dyn Any + Send + Sync> {
+    Box::new(T::default())
+}
+
+fn insert_value<T: Send + Sync + 'static>(
+    world: &mut World,
+    entity: u64,
+    value: Box<dyn Any + Send + Sync>,
+) {
+    let value: Box<dyn Any> = value;
+    world.add_component(entity, *value.downcast::<T>().unwrap());
+}
+
+fn map_value<T: MapEntities + 'static>(value: &mut dyn Any, map: &EntityMap) {
+    value.downcast_mut::<T>().unwrap().map_entities(map);
+}
+
+fn clone_components<T: Clone + 'static>(storage: &mut dyn ComponentStorage, src: u64, dst: u64) {
+    let storage = storage
+        .as_any_mut()
+        .downcast_mut::<TypedStorage<T>>()
+        .unwrap();
+    for component in storage.get_all(src) {
+        storage.insert(dst, component);
+    }
+    if !storage.is_enabled(src) {
+        storage.set_enabled(dst, false);
+    }
+}
+
+fn clone_storage<T: Clone + Send + Sync + 'static>(
+    storage: &dyn ComponentStorage,
+) -> Box<dyn ComponentStorage> {
+    let storage = storage.as_any().downcast_ref::<TypedStorage<T>>().unwrap();
+    Box::new(storage.clone())
+}
+
+/// Metadata of one component type
+///
+/// `size`, `align` and `drop` are known for every type. `clone` and `debug` are only
+/// available once the type was registered as cloneable / debuggable, since the World
+/// cannot require `Clone` or `Debug` from every component.
+#[derive(Clone)]
+pub struct ComponentInfo {
+    type_id: TypeId,
+    type_name: &'static str,
+    id: String,
+    size: usize,
+    align: usize,
+    drop: DropFn,
+    clone: Option<CloneFn>,
+    debug: Option<DebugFn>,
+    reflect: Option<(ReflectFn, ReflectMutFn)>,
+    default: Option<DefaultFn>,
+    /// Set with `default` or `clone` - adds a boxed value to an entity
+    insert: Option<InsertFn>,
+    map_entities: Option<MapEntitiesFn>,
+    clone_components: Option<CloneComponentsFn>,
+    clone_storage: Option<CloneStorageFn>,
+    state_hashed: bool,
+    replicated: bool,
+    version: u32,
+    /// Migration from each schema version to the next
+    migrations: Vec<(u32, MigrationFn)>,
+}
+
+impl ComponentInfo {
+    fn of<T: 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            id: std::any::type_name::<T>().to_string(),
+            size: std::mem::size_of::<T>(),
+            align: std::mem::align_of::<T>(),
+            drop: drop_value::<T>,
+            clone: None,
+            debug: None,
+            reflect: None,
+            default: None,
+            insert: None,
+            map_entities: None,
+            clone_components: None,
+            clone_storage: None,
+            state_hashed: false,
+            replicated: false,
+            version: 1,
+            migrations: Vec::new(),
+        }
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Rust type name (`std::any::type_name`) - for display only, it is not guaranteed
+    /// to stay the same between compiler versions
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Stable string ID - the type name unless one was given with `ComponentRegistry::set_id`
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    pub fn drop_fn(&self) -> DropFn {
+        self.drop
+    }
+
+    pub fn clone_fn(&self) -> Option<CloneFn> {
+        self.clone
+    }
+
+    pub fn debug_fn(&self) -> Option<DebugFn> {
+        self.debug
+    }
+
+    pub fn is_cloneable(&self) -> bool {
+        self.clone.is_some()
+    }
+
+    pub fn is_reflected(&self) -> bool {
+        self.reflect.is_some()
+    }
+
+    /// Reflected and default-constructible - needed to save and load it with a scene
+    pub fn is_serializable(&self) -> bool {
+        self.reflect.is_some() && self.default.is_some()
+    }
+
+    /// Included in `World::state_hash`
+    pub fn is_state_hashed(&self) -> bool {
+        self.state_hashed
+    }
+
+    /// Sent to clients by `ReplicationServer`
+    pub fn is_replicated(&self) -> bool {
+        self.replicated
+    }
+
+    pub fn default_fn(&self) -> Option<DefaultFn> {
+        self.default
+    }
+
+    pub(crate) fn insert_fn(&self) -> Option<InsertFn> {
+        self.insert
+    }
+
+    pub(crate) fn map_entities_fn(&self) -> Option<MapEntitiesFn> {
+        self.map_entities
+    }
+
+    /// View a value of this type as `Reflect`, if registered
+    pub fn reflect<'a>(&self, value: &'a dyn Any) -> Option<&'a dyn Reflect> {
+        self.reflect.map(|(reflect, _)| reflect(value))
+    }
+
+    pub fn reflect_mut<'a>(&self, value: &'a mut dyn Any) -> Option<&'a mut dyn Reflect> {
+        self.reflect.map(|(_, reflect_mut)| reflect_mut(value))
+    }
+
+    /// Format a value of this type with its `Debug` implementation, if registered
+    pub fn format(&self, value: &dyn Any) -> Option<String> {
+        struct Formatted<'a>(&'a dyn Any, DebugFn);
+        impl fmt::Debug for Formatted<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                (self.1)(self.0, f)
+            }
+        }
+        let debug = self.debug?;
+        Some(format!("{:?}", Formatted(value, debug)))
+    }
+
+    pub(crate) fn clone_components_fn(&self) -> Option<CloneComponentsFn> {
+        self.clone_components
+    }
+
+    pub(crate) fn clone_storage_fn(&self) -> Option<CloneStorageFn> {
+        self.clone_storage
+    }
+
+    /// Schema version of the saved fields - 1 unless set with `ComponentRegistry::set_version`
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Migration from schema version `from` to `from + 1`, if registered
+    pub fn migration(&self, from: u32) -> Option<MigrationFn> {
+        self.migrations
+            .iter()
+            .find(|(version, _)| *version == from)
+            .map(|(_, migrate)| *migrate)
+    }
+}
+
+impl fmt::Debug for ComponentInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentInfo")
+            .field("id", &self.id)
+            .field("type_name", &self.type_name)
+            .field("size", &self.size)
+            .field("align", &self.align)
+            .field("cloneable", &self.clone.is_some())
+            .field("debug", &self.debug.is_some())
+            .field("reflect", &self.reflect.is_some())
+            .field("default", &self.default.is_some())
+            .field("state_hashed", &self.state_hashed)
+            .field("replicated", &self.replicated)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Every component type of a World, by `TypeId` and by stable string ID
+///
+/// Types are registered automatically the first time a component of that type is added.
+/// Register them up front to give them a stable ID or clone / debug support:
+///
+/// ```
+/// # use ecs_hybrid::*;
+/// #[derive(Debug, Clone)]
+/// struct Armor(u32);
+///
+/// let mut world = World::new();
+/// world.registry_mut().set_id::<Armor>("Armor");
+/// world.registry_mut().register_clone::<Armor>();
+/// world.registry_mut().register_debug::<Armor>();
+///
+/// let info = world.registry().get_by_id("Armor").unwrap();
+/// assert_eq!(info.size(), 4);
+/// assert_eq!(info.format(&Armor(3)).unwrap(), "Armor(3)");
+/// ```
+#[derive(Default)]
+pub struct ComponentRegistry {
+    infos: Vec<ComponentInfo>,
+    by_type: HashMap<TypeId, usize>,
+    by_id: HashMap<String, usize>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a component type (no-op if it already is) - returns its metadata
+    pub fn register<T: Send + Sync + 'static>(&mut self) -> &ComponentInfo {
+        let index = self.index_of::<T>();
+        &self.infos[index]
+    }
+
+    fn index_of<T: 'static>(&mut self) -> usize {
+        if let Some(index) = self.by_type.get(&TypeId::of::<T>()) {
+            return *index;
+        }
+        let info = ComponentInfo::of::<T>();
+        let index = self.infos.len();
+        assert!(
+            !self.by_id.contains_key(&info.id),
+            "Component ID '{}' is already registered",
+            info.id
+        );
+        self.by_type.insert(info.type_id, index);
+        self.by_id.insert(info.id.clone(), index);
+        self.infos.push(info);
+        index
+    }
+
+    /// Give a component type a stable string ID (used by tooling and serialization)
+    ///
+    /// Panics if another type already uses the ID.
+    pub fn set_id<T: Send + Sync + 'static>(&mut self, id: &str) {
+        let index = self.index_of::<T>();
+        if let Some(owner) = self.by_id.get(id) {
+            assert!(
+                *owner == index,
+                "Cannot give {} the ID '{}': it is already used by {}",
+                std::any::type_name::<T>(),
+                id,
+                self.infos[*owner].type_name
+            );
+            return;
+        }
+        let info = &mut self.infos[index];
+        self.by_id.remove(&info.id);
+        info.id = id.to_string();
+        self.by_id.insert(info.id.clone(), index);
+    }
+
+    /// Record how to clone `T` - needed by `World::clone_entity` and `World::snapshot`
+    pub fn register_clone<T: Clone + Send + Sync + 'static>(&mut self) {
+        let index = self.index_of::<T>();
+        let info = &mut self.infos[index];
+        info.clone = Some(clone_value::<T>);
+        info.clone_components = Some(clone_components::<T>);
+        info.clone_storage = Some(clone_storage::<T>);
+        info.insert = Some(insert_value::<T>);
+    }
+
+    /// Record how to format `T` for debugging
+    pub fn register_debug<T: fmt::Debug + Send + Sync + 'static>(&mut self) {
+        let index = self.index_of::<T>();
+        self.infos[index].debug = Some(debug_value::<T>);
+    }
+
+    /// Make `T` reachable by `World::get_field` / `World::set_field`
+    pub fn register_reflect<T: Reflect>(&mut self) {
+        let index = self.index_of::<T>();
+        self.infos[index].reflect = Some((reflect_value::<T>, reflect_value_mut::<T>));
+    }
+
+    /// Make `T` saveable and loadable with scenes: reflected fields plus a default value
+    /// the loaded fields are written into
+    pub fn register_serializable<T: Reflect + Default>(&mut self) {
+        self.register_reflect::<T>();
+        let index = self.index_of::<T>();
+        self.infos[index].default = Some(default_value::<T>);
+        self.infos[index].insert = Some(insert_value::<T>);
+    }
+
+    /// Include `T` in `World::state_hash` - its reflected fields are hashed
+    pub fn register_state_hash<T: Reflect>(&mut self) {
+        self.register_reflect::<T>();
+        let index = self.index_of::<T>();
+        self.infos[index].state_hashed = true;
+    }
+
+    /// Replicate `T` from server to client Worlds - register it, with the same ID, on both
+    pub fn register_replicated<T: Reflect + Default>(&mut self) {
+        self.register_serializable::<T>();
+        let index = self.index_of::<T>();
+        self.infos[index].replicated = true;
+    }
+
+    /// Set the schema version `T` is saved with - bump it when its fields change and add a
+    /// migration from the previous version
+    pub fn set_version<T: Send + Sync + 'static>(&mut self, version: u32) {
+        assert!(version >= 1, "Schema versions start at 1");
+        let index = self.index_of::<T>();
+        self.infos[index].version = version;
+    }
+
+    /// Upgrade saved `T` data from schema version `from` to `from + 1`
+    ///
+    /// Loading data saved with an older version runs every step up to the current version.
+    pub fn add_migration<T: Send + Sync + 'static>(&mut self, from: u32, migrate: MigrationFn) {
+        let index = self.index_of::<T>();
+        let migrations = &mut self.infos[index].migrations;
+        migrations.retain(|(version, _)| *version != from);
+        migrations.push((from, migrate));
+    }
+
+    /// Remap the entity ids `T` holds when scenes are loaded
+    pub fn register_map_entities<T: MapEntities + Send + Sync + 'static>(&mut self) {
+        let index = self.index_of::<T>();
+        self.infos[index].map_entities = Some(map_value::<T>);
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&ComponentInfo> {
+        self.get_by_type_id(TypeId::of::<T>())
+    }
+
+    pub fn get_by_type_id(&self, type_id: TypeId) -> Option<&ComponentInfo> {
+        self.by_type.get(&type_id).map(|index| &self.infos[*index])
+    }
+
+    /// Look a component type up by its stable ID
+    pub fn get_by_id(&self, id: &str) -> Option<&ComponentInfo> {
+        self.by_id.get(id).map(|index| &self.infos[*index])
+    }
+
+    /// Registered types in registration order
+    pub fn iter(&self) -> impl Iterator<Item = &ComponentInfo> {
+        self.infos.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.infos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.infos.is_empty()
+    }
+}
@@ -0,0 +1,254 @@
+/// Network replication - server Worlds send the changes of replicated components to clients
+use crate::ecs_core::World;
+use crate::entity_map::EntityMap;
+use crate::patch::{self, State, WorldPatch};
+use crate::registry::ComponentInfo;
+use crate::snapshot::SnapshotError;
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+
+/// Carries packets between a server and a client - packets must arrive in order, once
+pub trait Transport {
+    fn send(&mut self, packet: Vec<u8>) -> io::Result<()>;
+    /// Next received packet, if any - never blocks
+    fn receive(&mut self) -> io::Result<Option<Vec<u8>>>;
+}
+
+/// In-process `Transport` over channels - for tests and local play
+pub struct ChannelTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+}
+
+impl ChannelTransport {
+    /// Two connected ends - what one sends, the other receives
+    pub fn pair() -> (ChannelTransport, ChannelTransport) {
+        let (a_sender, b_receiver) = mpsc::channel();
+        let (b_sender, a_receiver) = mpsc::channel();
+        (
+            ChannelTransport {
+                sender: a_sender,
+                receiver: a_receiver,
+            },
+            ChannelTransport {
+                sender: b_sender,
+                receiver: b_receiver,
+            },
+        )
+    }
+}
+
+impl Transport for ChannelTransport {
+    fn send(&mut self, packet: Vec<u8>) -> io::Result<()> {
+        self.sender
+            .send(packet)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the other end was dropped"))
+    }
+
+    fn receive(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match self.receiver.try_recv() {
+            Ok(packet) => Ok(Some(packet)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the other end was dropped",
+            )),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+
+/// Server side of replication - turns each tick of a World into a delta packet
+///
+/// Only component types registered with `ComponentRegistry::register_replicated` are sent,
+/// and only the entities holding at least one of them: an entity that loses its last
+/// replicated component is despawned on clients. Packets are `WorldPatch`es in the binary
+/// snapshot format.
+///
+/// ```
+/// # use ecs_hybrid::*;
+/// let (mut server_end, mut client_end) = ChannelTransport::pair();
+///
+/// let mut server_world = World::new();
+/// server_world.registry_mut().register_replicated::<Transform>();
+/// let mut server = ReplicationServer::new();
+///
+/// let mut client_world = World::new();
+/// client_world.registry_mut().register_replicated::<Transform>();
+/// client_world.create_entity(); // client-only entity - ids differ between the Worlds
+/// let mut client = ReplicationClient::new();
+///
+/// let player = server_world.create_entity();
+/// server_world.add_component(player, Transform::new(1.0, 2.0, 3.0));
+/// server_world.add_component(player, Health::new(100.0)); // not replicated
+///
+/// for tick in 0..3 {
+///     server_world.write_component::<Transform>(player).unwrap().x = tick as f32;
+///     server.send(&server_world, &mut server_end).unwrap();
+///     client.receive(&mut client_world, &mut client_end).unwrap();
+/// }
+///
+/// let local = client.client_entity(player).unwrap();
+/// assert_ne!(local, player);
+/// assert_eq!(client_world.get_component::<Transform>(local).unwrap().x, 2.0);
+/// assert!(client_world.get_component::<Health>(local).is_none());
+/// ```
+#[derive(Default)]
+pub struct ReplicationServer {
+    /// Replicated state as of the last delta
+    sent: State,
+}
+
+impl ReplicationServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Changes of the replicated state since the last call - empty when nothing changed
+    pub fn delta(&mut self, world: &World) -> WorldPatch {
+        let (state, patch) = self.pending(world);
+        self.sent = state;
+        patch
+    }
+
+    /// Send this tick's delta, if anything changed - returns whether a packet was sent
+    ///
+    /// The delta only counts as sent once the transport accepted it: after an error, the
+    /// next call sends these changes again.
+    pub fn send(
+        &mut self,
+        world: &World,
+        transport: &mut dyn Transport,
+    ) -> Result<bool, SnapshotError> {
+        let (state, patch) = self.pending(world);
+        if patch.is_empty() {
+            self.sent = state;
+            return Ok(false);
+        }
+        let mut packet = Vec::new();
+        patch.write(&mut packet)?;
+        transport.send(packet)?;
+        self.sent = state;
+        Ok(true)
+    }
+
+    /// Current replicated state and its changes since the last delta
+    fn pending(&self, world: &World) -> (State, WorldPatch) {
+        let state = State::capture(world, ComponentInfo::is_replicated, false);
+        let patch = self.sent.diff(&state);
+        (state, patch)
+    }
+
+    /// Forget what was sent, so the next delta holds the whole replicated state - for a
+    /// client that (re)connects
+    pub fn reset(&mut self) {
+        self.sent = State::default();
+    }
+}
+
+/// Client side of replication - applies server deltas to a World
+///
+/// Replicated entities get fresh ids in the client World; the client keeps the server id
+/// -> client id map, and remaps entity references in components registered with
+/// `ComponentRegistry::register_map_entities`.
+#[derive(Default)]
+pub struct ReplicationClient {
+    entities: EntityMap,
+}
+
+impl ReplicationClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Client entity of a server entity
+    pub fn client_entity(&self, server_entity: u64) -> Option<u64> {
+        self.entities.get(server_entity)
+    }
+
+    /// Server id -> client id of every replicated entity
+    pub fn entity_map(&self) -> &EntityMap {
+        &self.entities
+    }
+
+    /// Apply one server delta - on error the World, its entity id allocator and the entity
+    /// map are left untouched
+    pub fn apply(&mut self, world: &mut World, patch: &WorldPatch) -> Result<(), SnapshotError> {
+        // Check the delta with stand-in ids for the new entities (nothing is registered past
+        // the allocator), and reserve the real ids only once nothing can fail
+        let mut stand_in = world.next_entity_id();
+        let (translated, _) = self.translate(world, patch, || {
+            stand_in += 1;
+            stand_in - 1
+        })?;
+        let built = patch::prepare(world, &translated)?;
+
+        let (translated, mut entities) = self.translate(world, patch, || world.reserve_entity())?;
+        patch::commit(world, &translated, built, Some(&entities))?;
+        for &entity in &patch.despawned {
+            entities.remove(entity);
+        }
+        self.entities = entities;
+        Ok(())
+    }
+
+    /// A server delta in client ids, with the entity map extended by its spawned entities
+    /// (ids from `new_id`)
+    fn translate(
+        &self,
+        world: &World,
+        patch: &WorldPatch,
+        mut new_id: impl FnMut() -> u64,
+    ) -> Result<(WorldPatch, EntityMap), SnapshotError> {
+        let mut entities = self.entities.clone();
+        for &entity in &patch.spawned {
+            if entities.get(entity).is_none() {
+                entities.insert(entity, new_id());
+            }
+        }
+        let local = |entity: u64| {
+            entities
+                .get(entity)
+                .ok_or(SnapshotError::MissingEntity(entity))
+        };
+
+        let mut translated = WorldPatch {
+            next_entity_id: world.next_entity_id(),
+            ..Default::default()
+        };
+        for &entity in &patch.spawned {
+            translated.spawned.push(local(entity)?);
+        }
+        for &entity in &patch.despawned {
+            translated.despawned.push(local(entity)?);
+        }
+        for change in &patch.entities {
+            let mut change = change.clone();
+            change.entity = local(change.entity)?;
+            change.parent = change.parent.map(local).transpose()?;
+            translated.entities.push(change);
+        }
+        for change in &patch.components {
+            let mut change = change.clone();
+            change.entity = local(change.entity)?;
+            translated.components.push(change);
+        }
+        Ok((translated, entities))
+    }
+
+    /// Apply every packet the transport received - returns how many
+    pub fn receive(
+        &mut self,
+        world: &mut World,
+        transport: &mut dyn Transport,
+    ) -> Result<usize, SnapshotError> {
+        let mut applied = 0;
+        while let Some(packet) = transport.receive()? {
+            let patch = WorldPatch::read(&mut packet.as_slice())?;
+            self.apply(world, &patch)?;
+            applied += 1;
+        }
+        Ok(applied)
+    }
+}
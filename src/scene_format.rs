@@ -0,0 +1,448 @@
+/// Scene text format - human-readable save files, see SCENE_FORMAT.md
+use crate::behaviour::Script;
+use crate::ecs_core::World;
+use crate::entity_map::EntityMap;
+use crate::layers::LayerMask;
+use crate::migration::{self, BuildError, ComponentData, LoadReport, MigrationLog};
+use crate::reflect::Value;
+use crate::registry::ComponentInfo;
+use std::any::{Any, TypeId};
+use std::collections::HashSet;
+use std::fmt::{self, Write};
+
+/// First line of every scene file
+const HEADER: &str = "ecs_hybrid scene 1";
+
+/// Why a scene could not be saved or loaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneError {
+    /// Line of the scene text (1-based) - 0 when saving
+    pub line: usize,
+    /// Entity id as written in the scene text
+    pub entity: Option<u64>,
+    /// Component ID from the registry
+    pub component: Option<String>,
+    pub message: String,
+}
+
+impl SceneError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            entity: None,
+            component: None,
+            message: message.into(),
+        }
+    }
+
+    fn entity(mut self, entity: Option<u64>) -> Self {
+        self.entity = entity;
+        self
+    }
+
+    fn component(mut self, component: &str) -> Self {
+        self.component = Some(component.to_string());
+        self
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}: ", self.line)?;
+        }
+        if let Some(entity) = self.entity {
+            write!(f, "entity {}: ", entity)?;
+        }
+        if let Some(component) = &self.component {
+            write!(f, "component '{}': ", component)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// Saving
+
+pub(crate) fn save(world: &World) -> Result<String, SceneError> {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", HEADER);
+    for entity in world.entities() {
+        let _ = writeln!(out, "entity {}", entity);
+        if let Some(parent) = world.parent(entity) {
+            let _ = writeln!(out, "  parent {}", parent);
+        }
+        if !world.is_active_self(entity) {
+            let _ = writeln!(out, "  inactive");
+        }
+        let layers = world.layers(entity);
+        if layers != LayerMask::DEFAULT {
+            let _ = writeln!(out, "  layers {:#x}", layers.0);
+        }
+        for tag in world.tags(entity) {
+            let _ = writeln!(out, "  tag {:?}", tag);
+        }
+        for (info, count) in world.components_of(entity) {
+            // Scripts are behaviour code, not data - the game attaches them again after loading
+            if info.type_id() == TypeId::of::<Script>() {
+                continue;
+            }
+            if !info.is_serializable() {
+                return Err(SceneError::new(
+                    0,
+                    "type is not serializable - register it with ComponentRegistry::register_serializable",
+                )
+                .entity(Some(entity))
+                .component(info.id()));
+            }
+            let storage = world.read_column(info.type_id()).unwrap();
+            let mut flags = String::new();
+            if info.version() != 1 {
+                let _ = write!(flags, " v{}", info.version());
+            }
+            if !storage.is_enabled(entity) {
+                flags.push_str(" disabled");
+            }
+            for index in 0..count {
+                let value = storage.get_dyn(entity, index).unwrap();
+                let _ = writeln!(out, "  component {}{}", info.id(), flags);
+                // One `path = value` line per leaf field
+                info.reflect(value)
+                    .unwrap()
+                    .for_each_leaf(&mut |path, value| {
+                        let _ = writeln!(out, "    {} = {}", path, value);
+                    });
+            }
+        }
+    }
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// Loading
+
+struct LoadedComponent {
+    info: ComponentInfo,
+    line: usize,
+    enabled: bool,
+    /// Schema version the component was saved with
+    version: u32,
+    data: ComponentData,
+    /// Line of each field, for errors
+    field_lines: Vec<(String, usize)>,
+    /// Built from `data` once the whole text is parsed
+    value: Option<Box<dyn Any + Send + Sync>>,
+}
+
+struct LoadedEntity {
+    id: u64,
+    line: usize,
+    parent: Option<(u64, usize)>,
+    active: bool,
+    layers: Option<LayerMask>,
+    tags: Vec<String>,
+    components: Vec<LoadedComponent>,
+}
+
+pub(crate) fn load(world: &mut World, text: &str) -> Result<LoadReport, SceneError> {
+    let mut entities = parse(world, text)?;
+    let mut log = MigrationLog::default();
+    for entity in &mut entities {
+        for component in &mut entity.components {
+            component.value = Some(build(entity.id, component, &mut log)?);
+        }
+    }
+
+    // Everything parsed - only now touch the world
+    let mut map = EntityMap::new();
+    for entity in &entities {
+        map.insert(entity.id, world.create_entity());
+    }
+    for entity in entities {
+        let id = map.map(entity.id);
+        if let Some((parent, _)) = entity.parent {
+            world.set_parent(id, Some(map.map(parent)));
+        }
+        if !entity.active {
+            world.set_active(id, false);
+        }
+        if let Some(layers) = entity.layers {
+            world.set_layers(id, layers);
+        }
+        for tag in &entity.tags {
+            world.add_tag(id, tag);
+        }
+        for component in entity.components {
+            let mut value = component.value.unwrap();
+            if let Some(map_entities) = component.info.map_entities_fn() {
+                map_entities(value.as_mut(), &map);
+            }
+            (component.info.insert_fn().unwrap())(world, id, value);
+            if !component.enabled {
+                if let Some(storage) = world.column_mut(component.info.type_id()) {
+                    storage.set_enabled(id, false);
+                }
+            }
+        }
+    }
+    Ok(LoadReport {
+        entities: map,
+        migrations: log.into_migrations(),
+    })
+}
+
+/// Upgrade and write the parsed fields of a component into a value
+fn build(
+    entity: u64,
+    component: &mut LoadedComponent,
+    log: &mut MigrationLog,
+) -> Result<Box<dyn Any + Send + Sync>, SceneError> {
+    let data = std::mem::take(&mut component.data);
+    migration::build(&component.info, component.version, data, log).map_err(|error| {
+        let (line, message) = match error {
+            BuildError::Version(message) => (component.line, message),
+            BuildError::Field(path, error) => {
+                let line = component
+                    .field_lines
+                    .iter()
+                    .find(|(field, _)| *field == path)
+                    .map_or(component.line, |(_, line)| *line);
+                (line, error.to_string())
+            }
+        };
+        SceneError::new(line, message)
+            .entity(Some(entity))
+            .component(component.info.id())
+    })
+}
+
+fn parse(world: &World, text: &str) -> Result<Vec<LoadedEntity>, SceneError> {
+    let registry = world.registry();
+    let mut entities: Vec<LoadedEntity> = Vec::new();
+    let mut ids = HashSet::new();
+    let mut header = false;
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let content = raw.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        if !header {
+            if content != HEADER {
+                return Err(SceneError::new(
+                    line,
+                    format!("expected '{}' header, found '{}'", HEADER, content),
+                ));
+            }
+            header = true;
+            continue;
+        }
+        let (keyword, rest) = content.split_once(' ').unwrap_or((content, ""));
+        let rest = rest.trim();
+        // `path = value` - checked first so fields may be named like keywords
+        let is_field = keyword.contains('=') || rest.starts_with('=');
+
+        if keyword == "entity" && !is_field {
+            let id = rest
+                .parse()
+                .map_err(|_| SceneError::new(line, format!("invalid entity id '{}'", rest)))?;
+            if !ids.insert(id) {
+                return Err(SceneError::new(line, "entity is defined twice").entity(Some(id)));
+            }
+            entities.push(LoadedEntity {
+                id,
+                line,
+                parent: None,
+                active: true,
+                layers: None,
+                tags: Vec::new(),
+                components: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some(entity) = entities.last_mut() else {
+            return Err(SceneError::new(
+                line,
+                format!("'{}' before the first entity", keyword),
+            ));
+        };
+        let error = |message: String| SceneError::new(line, message).entity(Some(entity.id));
+
+        match keyword {
+            _ if is_field => {
+                let Some(component) = entity.components.last_mut() else {
+                    return Err(error(format!("field '{}' outside of a component", content)));
+                };
+                let id = component.info.id();
+                let (path, value) = content.split_once('=').unwrap();
+                let (path, value) = (path.trim(), value.trim());
+                let value = parse_value(value)
+                    .ok_or_else(|| error(format!("invalid value '{}'", value)).component(id))?;
+                component.data.set(path, value);
+                component.field_lines.push((path.to_string(), line));
+            }
+            "parent" => {
+                let parent = rest
+                    .parse()
+                    .map_err(|_| error(format!("invalid parent id '{}'", rest)))?;
+                entity.parent = Some((parent, line));
+            }
+            "inactive" => entity.active = false,
+            "layers" => {
+                let mask = rest
+                    .strip_prefix("0x")
+                    .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| error(format!("invalid layer mask '{}'", rest)))?;
+                entity.layers = Some(LayerMask(mask));
+            }
+            "tag" => match parse_value(rest) {
+                Some(Value::String(tag)) => entity.tags.push(tag),
+                _ => return Err(error(format!("invalid tag {}", rest))),
+            },
+            "component" => {
+                let mut words = rest.split_whitespace();
+                let id = words.next().unwrap_or_default();
+                let mut enabled = true;
+                let mut version = 1;
+                for flag in words {
+                    match flag {
+                        "disabled" => enabled = false,
+                        _ if flag.starts_with('v') && flag[1..].parse::<u32>().is_ok() => {
+                            version = flag[1..].parse().unwrap();
+                        }
+                        _ => {
+                            return Err(
+                                error(format!("unknown component flag '{}'", flag)).component(id)
+                            )
+                        }
+                    }
+                }
+                let info = registry
+                    .get_by_id(id)
+                    .ok_or_else(|| error("unknown component type".to_string()).component(id))?;
+                if !info.is_serializable() {
+                    return Err(error(
+                        "type is not serializable - register it with ComponentRegistry::register_serializable"
+                            .to_string(),
+                    )
+                    .component(id));
+                }
+                // One enabled flag covers every instance of a type on an entity
+                let earlier = entity
+                    .components
+                    .iter()
+                    .find(|c| c.info.type_id() == info.type_id());
+                if earlier.is_some_and(|earlier| earlier.enabled != enabled) {
+                    return Err(error(
+                        "instances of a component type must all be enabled or all be disabled"
+                            .to_string(),
+                    )
+                    .component(id));
+                }
+                entity.components.push(LoadedComponent {
+                    info: info.clone(),
+                    line,
+                    enabled,
+                    version,
+                    data: ComponentData::new(),
+                    field_lines: Vec::new(),
+                    value: None,
+                });
+            }
+            _ => return Err(error(format!("unknown keyword '{}'", keyword))),
+        }
+    }
+
+    if !header {
+        return Err(SceneError::new(0, format!("missing '{}' header", HEADER)));
+    }
+    for entity in &entities {
+        if let Some((parent, line)) = entity.parent {
+            if !ids.contains(&parent) {
+                return Err(SceneError::new(
+                    line,
+                    format!("parent {} is not in the scene", parent),
+                )
+                .entity(Some(entity.id)));
+            }
+        }
+    }
+    reject_cycles(&entities)?;
+    Ok(entities)
+}
+
+/// Parenting would silently fail on cycles - report them instead
+fn reject_cycles(entities: &[LoadedEntity]) -> Result<(), SceneError> {
+    let parent_of = |id: u64| {
+        entities
+            .iter()
+            .find(|e| e.id == id)
+            .and_then(|e| e.parent)
+            .map(|(parent, _)| parent)
+    };
+    for entity in entities {
+        let mut current = entity.parent.map(|(parent, _)| parent);
+        for _ in 0..entities.len() {
+            match current {
+                Some(id) if id == entity.id => {
+                    return Err(SceneError::new(entity.line, "entity is its own ancestor")
+                        .entity(Some(entity.id)));
+                }
+                Some(id) => current = parent_of(id),
+                None => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `true` / `false`, a quoted string, an integer or a float - the output of `Value`'s Display
+fn parse_value(text: &str) -> Option<Value> {
+    match text {
+        "true" => return Some(Value::Bool(true)),
+        "false" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    if let Some(quoted) = text.strip_prefix('"') {
+        return unescape(quoted.strip_suffix('"')?).map(Value::String);
+    }
+    if let Ok(value) = text.parse::<i64>() {
+        return Some(Value::Int(value));
+    }
+    text.parse::<f64>().ok().map(Value::Float)
+}
+
+/// Undo the escaping of `{:?}` on strings
+fn unescape(text: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            return None;
+        }
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            '0' => out.push('\0'),
+            '\\' => out.push('\\'),
+            '"' => out.push('"'),
+            '\'' => out.push('\''),
+            'u' => {
+                let rest: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                let code = u32::from_str_radix(rest.strip_prefix('{')?, 16).ok()?;
+                out.push(char::from_u32(code)?);
+            }
+            _ => return None,
+        }
+    }
+    Some(out)
+}
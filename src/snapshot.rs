@@ -0,0 +1,620 @@
+/// Binary world snapshots - compact save games
+///
+/// Layout (all integers little-endian):
+///
+/// ```text
+/// header   magic "ECSHSNAP" | format version u32 | reserved u32 | payload length u64 | checksum u64
+/// payload  next entity id u64
+///          entity count u32, per entity:
+///              id u64 | flags u8 (1 inactive, 2 has parent, 4 has layers, 8 has tags)
+///              [parent u64] [layers u64] [tag count u32, tags (string)]
+///          component table: type count u32, per type:
+///              ID (string) | schema version u32 |
+///              field count u32, per field: path (string) | kind u8
+///          columns, in table order - per type:
+///              entity count u32, per entity:
+///                  id u64 | enabled u8 | instance count u32, per instance one value per field
+/// string   byte length u32 + UTF-8 bytes
+/// value    by field kind: 0 bool u8 | 1 integer i64 | 2 f64 | 3 string | 4 f32
+/// ```
+///
+/// The checksum is the 64-bit FNV-1a hash of the payload. Format version 1 had no schema
+/// versions in the component table - its components are read as schema version 1.
+use crate::ecs_core::World;
+use crate::entity_map::EntityMap;
+use crate::layers::LayerMask;
+use crate::migration::{self, BuildError, ComponentData, LoadReport, MigrationLog};
+use crate::reflect::{ReflectError, Value};
+use crate::registry::ComponentInfo;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 8] = b"ECSHSNAP";
+const VERSION: u32 = 2;
+const HEADER_LEN: usize = 32;
+const INACTIVE: u8 = 1;
+const HAS_PARENT: u8 = 2;
+const HAS_LAYERS: u8 = 4;
+const HAS_TAGS: u8 = 8;
+
+/// Why a snapshot could not be written or read
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The data does not start with the snapshot magic bytes
+    NotASnapshot,
+    UnsupportedVersion(u32),
+    /// The payload does not match the checksum in the header
+    ChecksumMismatch {
+        expected: u64,
+        found: u64,
+    },
+    /// The payload ended early or holds an invalid value
+    Corrupt(String),
+    /// No component type is registered under this ID
+    UnknownComponent(String),
+    /// A component type present on an entity is not reflected and default-constructible
+    NotSerializable {
+        entity: u64,
+        component: String,
+    },
+    /// A stored field could not be written into the component
+    Field {
+        entity: u64,
+        component: String,
+        error: ReflectError,
+    },
+    /// A patch changes an entity the World does not have
+    MissingEntity(u64),
+    /// The stored schema version of a component cannot be upgraded to the registered one
+    Migration {
+        entity: u64,
+        component: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "I/O error: {}", error),
+            SnapshotError::NotASnapshot => write!(f, "not a world snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch: expected {:#018x}, found {:#018x}",
+                expected, found
+            ),
+            SnapshotError::Corrupt(message) => write!(f, "corrupt snapshot: {}", message),
+            SnapshotError::UnknownComponent(id) => write!(f, "unknown component '{}'", id),
+            SnapshotError::NotSerializable { entity, component } => write!(
+                f,
+                "entity {}: component '{}' is not serializable",
+                entity, component
+            ),
+            SnapshotError::Field {
+                entity,
+                component,
+                error,
+            } => write!(f, "entity {}: component '{}': {}", entity, component, error),
+            SnapshotError::MissingEntity(entity) => write!(f, "entity {} does not exist", entity),
+            SnapshotError::Migration {
+                entity,
+                component,
+                message,
+            } => write!(
+                f,
+                "entity {}: component '{}': {}",
+                entity, component, message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+/// 64-bit FNV-1a
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// Encoding
+
+/// How the values of a field are stored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Kind {
+    Bool = 0,
+    Int = 1,
+    Float = 2,
+    String = 3,
+    F32 = 4,
+}
+
+impl Kind {
+    pub(crate) fn of(type_name: &str, value: &Value) -> Self {
+        match value {
+            Value::Bool(_) => Kind::Bool,
+            Value::Int(_) => Kind::Int,
+            Value::Float(_) if type_name == "f32" => Kind::F32,
+            Value::Float(_) => Kind::Float,
+            Value::String(_) => Kind::String,
+        }
+    }
+
+    pub(crate) fn from_u8(kind: u8) -> Option<Self> {
+        [Kind::Bool, Kind::Int, Kind::Float, Kind::String, Kind::F32]
+            .into_iter()
+            .find(|k| *k as u8 == kind)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Encoder {
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+
+    pub(crate) fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    /// Entity id and state - most entities are active roots on the default layer, so only
+    /// what differs is stored
+    pub(crate) fn entity(
+        &mut self,
+        id: u64,
+        parent: Option<u64>,
+        active: bool,
+        layers: LayerMask,
+        tags: &[&str],
+    ) {
+        let mut flags = 0;
+        if !active {
+            flags |= INACTIVE;
+        }
+        if parent.is_some() {
+            flags |= HAS_PARENT;
+        }
+        if layers != LayerMask::DEFAULT {
+            flags |= HAS_LAYERS;
+        }
+        if !tags.is_empty() {
+            flags |= HAS_TAGS;
+        }
+        self.u64(id);
+        self.u8(flags);
+        if let Some(parent) = parent {
+            self.u64(parent);
+        }
+        if flags & HAS_LAYERS != 0 {
+            self.u64(layers.0);
+        }
+        if flags & HAS_TAGS != 0 {
+            self.len(tags.len());
+            for tag in tags {
+                self.str(tag);
+            }
+        }
+    }
+
+    /// Returns false if the value does not match the field kind
+    pub(crate) fn value(&mut self, kind: Kind, value: &Value) -> bool {
+        match (kind, value) {
+            (Kind::Bool, Value::Bool(value)) => self.u8(*value as u8),
+            (Kind::Int, Value::Int(value)) => self.u64(*value as u64),
+            (Kind::Float, Value::Float(value)) => self.u64(value.to_bits()),
+            (Kind::F32, Value::Float(value)) => self.u32((*value as f32).to_bits()),
+            (Kind::String, Value::String(value)) => self.str(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+pub(crate) fn write(world: &World, out: &mut impl Write) -> Result<(), SnapshotError> {
+    let mut payload = Encoder::default();
+    payload.u64(world.next_entity_id());
+
+    let entities: Vec<u64> = world.entities().collect();
+    payload.len(entities.len());
+    for &entity in &entities {
+        payload.entity(
+            entity,
+            world.parent(entity),
+            world.is_active_self(entity),
+            world.layers(entity),
+            &world.tags(entity),
+        );
+    }
+
+    // Component table - every registered type with at least one instance
+    let mut columns: Vec<(&ComponentInfo, Vec<u64>)> = Vec::new();
+    for info in world.registry().iter() {
+        let Some(storage) = world.read_column(info.type_id()) else {
+            continue;
+        };
+        let mut holders = Vec::new();
+        storage.for_each_entity(&mut |entity| holders.push(entity));
+        if holders.is_empty() {
+            continue;
+        }
+        holders.sort_unstable();
+        if !info.is_serializable() {
+            return Err(SnapshotError::NotSerializable {
+                entity: holders[0],
+                component: info.id().to_string(),
+            });
+        }
+        columns.push((info, holders));
+    }
+    payload.len(columns.len());
+    let mut kinds = Vec::new();
+    for &(info, _) in &columns {
+        payload.str(info.id());
+        payload.u32(info.version());
+        let fields = fields_of(info);
+        payload.len(fields.len());
+        for (path, kind) in &fields {
+            payload.str(path);
+            payload.u8(*kind as u8);
+        }
+        kinds.push(fields.into_iter().map(|(_, kind)| kind).collect::<Vec<_>>());
+    }
+
+    for (&(info, ref holders), kinds) in columns.iter().zip(&kinds) {
+        let storage = world.read_column(info.type_id()).unwrap();
+        payload.len(holders.len());
+        for &entity in holders {
+            let count = storage.count(entity);
+            payload.u64(entity);
+            payload.u8(storage.is_enabled(entity) as u8);
+            payload.len(count);
+            for index in 0..count {
+                let value = info
+                    .reflect(storage.get_dyn(entity, index).unwrap())
+                    .unwrap();
+                let mut index = 0;
+                let mut valid = true;
+                value.for_each_leaf(&mut |_, value| {
+                    valid &= kinds
+                        .get(index)
+                        .is_some_and(|kind| payload.value(*kind, &value));
+                    index += 1;
+                });
+                if !valid || index != kinds.len() {
+                    return Err(SnapshotError::Corrupt(format!(
+                        "entity {}: fields of component '{}' do not match its default value",
+                        entity,
+                        info.id()
+                    )));
+                }
+            }
+        }
+    }
+
+    write_framed(MAGIC, &payload.bytes, out)
+}
+
+/// Write the header (magic, format version, length, checksum) and the payload
+pub(crate) fn write_framed(
+    magic: &[u8; 8],
+    payload: &[u8],
+    out: &mut impl Write,
+) -> Result<(), SnapshotError> {
+    let mut header = Encoder::default();
+    header.bytes.extend_from_slice(magic);
+    header.u32(VERSION);
+    header.u32(0);
+    header.u64(payload.len() as u64);
+    header.u64(checksum(payload));
+    out.write_all(&header.bytes)?;
+    out.write_all(payload)?;
+    Ok(())
+}
+
+/// Read and verify the header, then the payload - returns the format version and payload
+pub(crate) fn read_framed(
+    magic: &[u8; 8],
+    input: &mut impl Read,
+) -> Result<(u32, Vec<u8>), SnapshotError> {
+    let mut header = [0u8; HEADER_LEN];
+    input.read_exact(&mut header)?;
+    let mut decoder = Decoder { bytes: &header };
+    if decoder.take(magic.len())? != magic {
+        return Err(SnapshotError::NotASnapshot);
+    }
+    let version = decoder.u32()?;
+    if version == 0 || version > VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    decoder.u32()?;
+    let len = decoder.u64()?;
+    let expected = decoder.u64()?;
+
+    let mut payload = Vec::new();
+    input.take(len).read_to_end(&mut payload)?;
+    if payload.len() as u64 != len {
+        return Err(SnapshotError::Corrupt("unexpected end of data".to_string()));
+    }
+    let found = checksum(&payload);
+    if found != expected {
+        return Err(SnapshotError::ChecksumMismatch { expected, found });
+    }
+    Ok((version, payload))
+}
+
+/// Leaf field paths and kinds of a component type, in the order its values are written
+fn fields_of(info: &ComponentInfo) -> Vec<(String, Kind)> {
+    let value = (info.default_fn().unwrap())();
+    let value: &dyn Any = value.as_ref();
+    let reflect = info.reflect(value).unwrap();
+    let mut fields = Vec::new();
+    reflect.for_each_leaf(&mut |path, value| {
+        let type_name = reflect.path(path).unwrap().type_name();
+        fields.push((path.to_string(), Kind::of(type_name, &value)));
+    });
+    fields
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// Decoding
+
+pub(crate) struct Decoder<'a> {
+    pub(crate) bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotError::Corrupt("unexpected end of data".to_string()));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn len(&mut self) -> Result<usize, SnapshotError> {
+        Ok(self.u32()? as usize)
+    }
+
+    pub(crate) fn str(&mut self) -> Result<String, SnapshotError> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| SnapshotError::Corrupt("invalid UTF-8 string".to_string()))
+    }
+
+    pub(crate) fn entity(&mut self) -> Result<DecodedEntity, SnapshotError> {
+        let id = self.u64()?;
+        let flags = self.u8()?;
+        let parent = match flags & HAS_PARENT {
+            0 => None,
+            _ => Some(self.u64()?),
+        };
+        let layers = match flags & HAS_LAYERS {
+            0 => LayerMask::DEFAULT,
+            _ => LayerMask(self.u64()?),
+        };
+        let tags = match flags & HAS_TAGS {
+            0 => Vec::new(),
+            _ => (0..self.len()?)
+                .map(|_| self.str())
+                .collect::<Result<_, _>>()?,
+        };
+        Ok(DecodedEntity {
+            id,
+            parent,
+            active: flags & INACTIVE == 0,
+            layers,
+            tags,
+        })
+    }
+
+    pub(crate) fn value(&mut self, kind: Kind) -> Result<Value, SnapshotError> {
+        Ok(match kind {
+            Kind::Bool => Value::Bool(self.u8()? != 0),
+            Kind::Int => Value::Int(self.u64()? as i64),
+            Kind::Float => Value::Float(f64::from_bits(self.u64()?)),
+            Kind::F32 => Value::Float(f32::from_bits(self.u32()?) as f64),
+            Kind::String => Value::String(self.str()?),
+        })
+    }
+}
+
+/// Everything a snapshot holds, decoded before the world is touched
+pub(crate) struct DecodedEntity {
+    pub(crate) id: u64,
+    pub(crate) parent: Option<u64>,
+    pub(crate) active: bool,
+    pub(crate) layers: LayerMask,
+    pub(crate) tags: Vec<String>,
+}
+
+pub(crate) struct DecodedComponent {
+    pub(crate) info: ComponentInfo,
+    pub(crate) entity: u64,
+    pub(crate) enabled: bool,
+    pub(crate) value: Box<dyn Any + Send + Sync>,
+}
+
+/// Reject entity tables the world cannot hold: ids the header's allocator never handed out
+/// (which would also size the dense per-entity tables by an arbitrary id), duplicates,
+/// missing parents and parent cycles - returns the set of ids
+fn validate_entities(
+    next_entity_id: u64,
+    entities: &[DecodedEntity],
+) -> Result<HashSet<u64>, SnapshotError> {
+    let mut ids = HashSet::with_capacity(entities.len());
+    for entity in entities {
+        if entity.id >= next_entity_id {
+            return Err(SnapshotError::Corrupt(format!(
+                "entity {} is not below the next entity id {}",
+                entity.id, next_entity_id
+            )));
+        }
+        if !ids.insert(entity.id) {
+            return Err(SnapshotError::Corrupt(format!(
+                "entity {} is listed twice",
+                entity.id
+            )));
+        }
+    }
+    let parents: HashMap<u64, u64> = entities
+        .iter()
+        .filter_map(|entity| Some((entity.id, entity.parent?)))
+        .collect();
+    for (&entity, &parent) in &parents {
+        if !ids.contains(&parent) {
+            return Err(SnapshotError::Corrupt(format!(
+                "parent {} of entity {} is not in the entity table",
+                parent, entity
+            )));
+        }
+        // A chain longer than the table loops
+        let mut current = Some(parent);
+        for _ in 0..=parents.len() {
+            current = current.and_then(|id| parents.get(&id).copied());
+        }
+        if current.is_some() {
+            return Err(SnapshotError::Corrupt(format!(
+                "the parent chain of entity {} loops",
+                entity
+            )));
+        }
+    }
+    Ok(ids)
+}
+
+pub(crate) fn read(world: &mut World, input: &mut impl Read) -> Result<LoadReport, SnapshotError> {
+    let (version, payload) = read_framed(MAGIC, input)?;
+    let mut decoder = Decoder { bytes: &payload };
+    let next_entity_id = decoder.u64()?;
+    let mut entities = Vec::new();
+    for _ in 0..decoder.len()? {
+        entities.push(decoder.entity()?);
+    }
+    let ids = validate_entities(next_entity_id, &entities)?;
+
+    let mut table = Vec::new();
+    for _ in 0..decoder.len()? {
+        let id = decoder.str()?;
+        let info = world
+            .registry()
+            .get_by_id(&id)
+            .filter(|info| info.is_serializable())
+            .ok_or(SnapshotError::UnknownComponent(id))?
+            .clone();
+        let schema = match version {
+            1 => 1,
+            _ => decoder.u32()?,
+        };
+        let mut fields = Vec::new();
+        for _ in 0..decoder.len()? {
+            let path = decoder.str()?;
+            let kind = decoder.u8()?;
+            let kind = Kind::from_u8(kind)
+                .ok_or_else(|| SnapshotError::Corrupt(format!("invalid field kind {}", kind)))?;
+            fields.push((path, kind));
+        }
+        table.push((info, schema, fields));
+    }
+
+    let mut log = MigrationLog::default();
+    let mut components = Vec::new();
+    for (info, schema, fields) in &table {
+        for _ in 0..decoder.len()? {
+            let entity = decoder.u64()?;
+            if !ids.contains(&entity) {
+                return Err(SnapshotError::Corrupt(format!(
+                    "component {} of entity {}, which is not in the entity table",
+                    info.id(),
+                    entity
+                )));
+            }
+            let enabled = decoder.u8()? != 0;
+            for _ in 0..decoder.len()? {
+                let mut data = ComponentData::new();
+                for (path, kind) in fields {
+                    data.set(path, decoder.value(*kind)?);
+                }
+                let component = info.id().to_string();
+                let value =
+                    migration::build(info, *schema, data, &mut log).map_err(
+                        |error| match error {
+                            BuildError::Version(message) => SnapshotError::Migration {
+                                entity,
+                                component,
+                                message,
+                            },
+                            BuildError::Field(_, error) => SnapshotError::Field {
+                                entity,
+                                component,
+                                error,
+                            },
+                        },
+                    )?;
+                components.push(DecodedComponent {
+                    info: info.clone(),
+                    entity,
+                    enabled,
+                    value,
+                });
+            }
+        }
+    }
+    if !decoder.bytes.is_empty() {
+        return Err(SnapshotError::Corrupt("trailing data".to_string()));
+    }
+
+    // Snapshots keep entity ids
+    let mut map = EntityMap::new();
+    for entity in &entities {
+        map.insert(entity.id, entity.id);
+    }
+    world.restore_decoded(next_entity_id, entities, components);
+    Ok(LoadReport {
+        entities: map,
+        migrations: log.into_migrations(),
+    })
+}
@@ -0,0 +1,158 @@
+/// Deterministic world hashing - lockstep desync detection
+use crate::ecs_core::World;
+use crate::reflect::Value;
+
+/// Hash of the simulation state of a World, with a breakdown per component type
+///
+/// Two Worlds that ran the same simulation have equal hashes on every machine and build:
+/// entities and components are visited in id order (never in `HashMap` order) and values
+/// are hashed by their bytes with 64-bit FNV-1a. Floats are hashed bit for bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateHash {
+    /// Hash of everything below
+    pub total: u64,
+    /// Entity ids, id allocator, hierarchy, active state, layers and tags
+    pub entities: u64,
+    /// One hash per component type registered with `ComponentRegistry::register_state_hash`,
+    /// by component ID, sorted by ID
+    pub components: Vec<(String, u64)>,
+}
+
+impl StateHash {
+    /// IDs of the component types whose hashes differ between two Worlds (or that only one
+    /// of them hashes) - compare `entities` for the entity-level state
+    pub fn diverged<'a>(&'a self, other: &'a StateHash) -> Vec<&'a str> {
+        let mut diverged: Vec<&str> = self
+            .components
+            .iter()
+            .filter(|(id, hash)| other.component(id) != Some(*hash))
+            .map(|(id, _)| id.as_str())
+            .collect();
+        diverged.extend(
+            other
+                .components
+                .iter()
+                .filter(|(id, _)| self.component(id).is_none())
+                .map(|(id, _)| id.as_str()),
+        );
+        diverged
+    }
+
+    /// Hash of one component type
+    pub fn component(&self, id: &str) -> Option<u64> {
+        self.components
+            .iter()
+            .find(|(component, _)| component == id)
+            .map(|(_, hash)| *hash)
+    }
+}
+
+/// 64-bit FNV-1a, fed explicitly little-endian so it does not depend on the platform
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.bytes(&[value]);
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.bytes(value.as_bytes());
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Bool(value) => {
+                self.u8(0);
+                self.u8(*value as u8);
+            }
+            Value::Int(value) => {
+                self.u8(1);
+                self.u64(*value as u64);
+            }
+            Value::Float(value) => {
+                self.u8(2);
+                self.u64(value.to_bits());
+            }
+            Value::String(value) => {
+                self.u8(3);
+                self.str(value);
+            }
+        }
+    }
+}
+
+pub(crate) fn hash(world: &World) -> StateHash {
+    let mut entities: Vec<u64> = world.entities().collect();
+    entities.sort_unstable();
+
+    let mut hasher = Fnv::new();
+    hasher.u64(world.next_entity_id());
+    hasher.u64(entities.len() as u64);
+    for &entity in &entities {
+        hasher.u64(entity);
+        hasher.u64(world.parent(entity).map_or(u64::MAX, |parent| parent));
+        hasher.u8(world.is_active_self(entity) as u8);
+        hasher.u64(world.layers(entity).0);
+        let tags = world.tags(entity);
+        hasher.u64(tags.len() as u64);
+        for tag in tags {
+            hasher.str(tag);
+        }
+    }
+    let entities_hash = hasher.0;
+
+    let mut components = Vec::new();
+    for info in world
+        .registry()
+        .iter()
+        .filter(|info| info.is_state_hashed())
+    {
+        let mut hasher = Fnv::new();
+        if let Some(storage) = world.read_column(info.type_id()) {
+            let mut holders = Vec::new();
+            storage.for_each_entity(&mut |entity| holders.push(entity));
+            holders.sort_unstable();
+            for entity in holders {
+                let count = storage.count(entity);
+                hasher.u64(entity);
+                hasher.u8(storage.is_enabled(entity) as u8);
+                hasher.u64(count as u64);
+                for index in 0..count {
+                    let value = info
+                        .reflect(storage.get_dyn(entity, index).unwrap())
+                        .unwrap();
+                    value.for_each_leaf(&mut |_, value| hasher.value(&value));
+                }
+            }
+        }
+        components.push((info.id().to_string(), hasher.0));
+    }
+    components.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut hasher = Fnv::new();
+    hasher.u64(entities_hash);
+    for (id, hash) in &components {
+        hasher.str(id);
+        hasher.u64(*hash);
+    }
+    StateHash {
+        total: hasher.0,
+        entities: entities_hash,
+        components,
+    }
+}
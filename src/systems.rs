@@ -1,6 +1,10 @@
 /// Game systems - the parallel execution units
+use crate::behaviour;
 use crate::ecs_core::World;
+use crate::game_object::Scene;
+use crate::world_snapshot::WorldSnapshot;
 use parking_lot::RwLock;
+use std::collections::VecDeque;
 use std::sync::Arc;
 
 /// Trait for systems that can run in parallel
@@ -11,12 +15,19 @@ pub trait System: Send + Sync {
 /// System executor - manages parallel system execution
 pub struct SystemExecutor {
     systems: Vec<Box<dyn System>>,
+    game_systems: Vec<Box<dyn GameSystem>>,
+    /// Snapshots of the last frames, oldest first
+    history: VecDeque<WorldSnapshot>,
+    history_len: usize,
 }
 
 impl SystemExecutor {
     pub fn new() -> Self {
         Self {
             systems: Vec::new(),
+            game_systems: Vec::new(),
+            history: VecDeque::new(),
+            history_len: 0,
         }
     }
 
@@ -24,9 +35,85 @@ impl SystemExecutor {
         self.systems.push(Box::new(system));
     }
 
+    /// Add a scene-level system - only run by `run()`
+    pub fn add_game_system<S: GameSystem + 'static>(&mut self, system: S) {
+        self.game_systems.push(Box::new(system));
+    }
+
+    /// Keep a `World::snapshot` of the end of each of the last `frames` frames, for
+    /// `rewind` - 0 (the default) keeps none
+    ///
+    /// Components that are not cloneable, like scripts, are not recorded: rewinding leaves
+    /// them as they are (see `World::snapshot`).
+    ///
+    /// Recording a frame copies the entity id list, and shares everything else with the
+    /// previous snapshot except what the frame changed: each component type written during
+    /// the frame is copied whole, and so is each of the hierarchy, layer masks, tags, Name
+    /// index and prefab links the frame touched. A frame that only moves entities costs the
+    /// id list plus one copy of the `Transform` column.
+    ///
+    /// ```
+    /// # use ecs_hybrid::*;
+    /// struct Damage;
+    /// impl System for Damage {
+    ///     fn execute(&mut self, world: &mut World, _delta_time: f32) {
+    ///         world.write_component::<Health>(0).unwrap().current -= 10.0;
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// let player = world.create_entity();
+    /// world.add_component(player, Health::new(100.0));
+    ///
+    /// let mut executor = SystemExecutor::new();
+    /// executor.add_system(Damage);
+    /// executor.keep_history(8);
+    /// for _ in 0..5 {
+    ///     executor.execute(&mut world, 1.0 / 60.0);
+    /// }
+    /// assert!(executor.rewind(&mut world, 2));
+    /// assert_eq!(world.get_component::<Health>(player).unwrap().current, 70.0);
+    /// assert_eq!(executor.history().count(), 3);
+    /// ```
+    pub fn keep_history(&mut self, frames: usize) {
+        self.history_len = frames;
+        while self.history.len() > frames {
+            self.history.pop_front();
+        }
+    }
+
+    /// Retained frame snapshots, oldest first
+    pub fn history(&self) -> impl Iterator<Item = &WorldSnapshot> {
+        self.history.iter()
+    }
+
+    /// Put the World back in the state it had `frames` frames ago (0 = the end of the last
+    /// frame) and drop the newer snapshots. Returns false if that frame is not retained
+    pub fn rewind(&mut self, world: &mut World, frames: usize) -> bool {
+        if frames >= self.history.len() {
+            return false;
+        }
+        self.history.truncate(self.history.len() - frames);
+        world.restore(self.history.back().unwrap());
+        true
+    }
+
+    /// Drop every retained snapshot - e.g. after loading a level
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
     /// Execute all systems - in real implementation, this would use rayon or similar
     /// for parallel execution
+    ///
+    /// Only runs the ECS systems: game systems, scripts and coroutines live on the Scene and
+    /// are advanced by `run`.
     pub fn execute(&mut self, world: &mut World, delta_time: f32) {
+        self.execute_systems(world, delta_time);
+        self.record(world);
+    }
+
+    fn execute_systems(&mut self, world: &mut World, delta_time: f32) {
         // In a real implementation, systems would run in parallel here
         // For simplicity, we run them sequentially
         // With proper ECS design, systems that don't conflict can run in parallel
@@ -34,6 +121,36 @@ impl SystemExecutor {
             system.execute(world, delta_time);
         }
     }
+
+    /// Snapshot the end of a frame into the history ring buffer
+    fn record(&mut self, world: &World) {
+        if self.history_len == 0 {
+            return;
+        }
+        if self.history.len() == self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(world.snapshot());
+    }
+
+    /// Run one frame on a scene: ECS systems under the world write lock, then game systems,
+    /// scripts and coroutines with the lock released so they can use the Unity-like Entity
+    /// API - entities destroyed during the frame are then removed (after their scripts'
+    /// `on_destroy`), and the frame is recorded if `keep_history` is set
+    pub fn run(&mut self, scene: &Scene, delta_time: f32) {
+        {
+            let world = scene.world();
+            let mut world = world.write();
+            self.execute_systems(&mut world, delta_time);
+        }
+        for system in &mut self.game_systems {
+            system.update(scene.world(), delta_time);
+        }
+        scene.update_scripts(delta_time);
+        scene.update_coroutines(delta_time);
+        behaviour::destroy_pending(scene);
+        self.record(&scene.world().read());
+    }
 }
 
 impl Default for SystemExecutor {
@@ -42,7 +159,7 @@ impl Default for SystemExecutor {
     }
 }
 
-// Example system trait for user convenience
+/// Scene-level system for user convenience - runs without the world lock held
 pub trait GameSystem {
     fn update(&mut self, world: Arc<RwLock<World>>, delta_time: f32);
 }
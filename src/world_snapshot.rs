@@ -0,0 +1,76 @@
+/// In-memory world snapshots - rollback and undo
+use crate::ecs_core::{ColumnVersion, ComponentStorage};
+use crate::hierarchy::Hierarchy;
+use crate::layers::EntityMasks;
+use crate::lookup::{LookupIndex, TagIndex};
+use crate::prefab::PrefabLink;
+use crate::registry::CloneStorageFn;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Copy of a whole World, made by `World::snapshot` and put back with `World::restore`
+///
+/// Component columns are shared between snapshots (and with the World's snapshot cache)
+/// until they are written, so keeping many consecutive snapshots only costs memory for the
+/// component types that changed between them. The hierarchy, layer masks, tags, Name index
+/// and prefab links are shared the same way, each as a whole: the World copies one the
+/// first time it changes it after a snapshot.
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    pub(crate) next_entity_id: u64,
+    pub(crate) entities: Vec<u64>,
+    pub(crate) hierarchy: Arc<Hierarchy>,
+    pub(crate) masks: Arc<EntityMasks>,
+    pub(crate) tags: Arc<TagIndex>,
+    pub(crate) lookup: Arc<LookupIndex>,
+    pub(crate) prefab_links: Arc<HashMap<u64, PrefabLink>>,
+    pub(crate) columns: Vec<SnapshotColumn>,
+    /// Component types that were present but not cloneable
+    pub(crate) skipped: Vec<(TypeId, &'static str)>,
+}
+
+/// Read-only copy of one column
+#[derive(Clone)]
+pub(crate) struct SnapshotColumn {
+    pub(crate) type_id: TypeId,
+    /// Version of the World's column the copy was made from
+    pub(crate) version: ColumnVersion,
+    pub(crate) storage: Arc<dyn ComponentStorage>,
+    pub(crate) clone: CloneStorageFn,
+}
+
+impl WorldSnapshot {
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn contains_entity(&self, entity: u64) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    /// Id the World's allocator was at - after restoring, new entities get this id or, if
+    /// the World had already handed it out, a later one
+    pub fn next_entity_id(&self) -> u64 {
+        self.next_entity_id
+    }
+
+    /// Type names of the components left out because they were not registered with
+    /// `World::register_clone` - `restore` does not touch them
+    pub fn skipped_components(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.skipped.iter().map(|(_, name)| *name)
+    }
+
+    /// Whether a column is shared with another snapshot, not copied - two snapshots taken
+    /// without writing `T` in between share it
+    pub fn shares_column_with<T: 'static>(&self, other: &WorldSnapshot) -> bool {
+        let column = |snapshot: &WorldSnapshot| {
+            snapshot
+                .columns
+                .iter()
+                .find(|column| column.type_id == TypeId::of::<T>())
+                .map(|column| Arc::as_ptr(&column.storage).cast::<()>())
+        };
+        column(self).is_some() && column(self) == column(other)
+    }
+}
@@ -0,0 +1,110 @@
+/// Script lifecycle: awake, start, update and on_destroy order, and deferred destruction
+use ecs_hybrid::*;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+struct Recorder {
+    name: &'static str,
+    log: Log,
+}
+
+impl Recorder {
+    fn push(&self, event: &str) {
+        self.log.lock().push(format!("{} {}", self.name, event));
+    }
+}
+
+impl Behaviour for Recorder {
+    fn awake(&mut self, _entity: &Entity, _scene: &Scene) {
+        self.push("awake");
+    }
+
+    fn start(&mut self, _entity: &Entity, _scene: &Scene) {
+        self.push("start");
+    }
+
+    fn update(&mut self, _entity: &Entity, _scene: &Scene, _delta_time: f32) {
+        self.push("update");
+    }
+
+    fn on_destroy(&mut self, entity: &Entity, _scene: &Scene) {
+        // Components are still there while on_destroy runs
+        let health = entity.with_component::<Health, _>(|health| health.current);
+        self.push(&format!("on_destroy {:?}", health));
+    }
+}
+
+fn recorded(log: &Log) -> Vec<String> {
+    std::mem::take(&mut *log.lock())
+}
+
+#[test]
+fn lifecycle_order() {
+    let scene = Scene::new();
+    let log = Log::default();
+    for name in ["a", "b"] {
+        scene
+            .instantiate()
+            .add_component(Health::new(10.0))
+            .add_behaviour(Recorder {
+                name,
+                log: log.clone(),
+            });
+    }
+
+    scene.update_scripts(0.1);
+    assert_eq!(
+        recorded(&log),
+        ["a awake", "b awake", "a start", "b start", "a update", "b update"]
+    );
+
+    scene.update_scripts(0.1);
+    assert_eq!(recorded(&log), ["a update", "b update"]);
+}
+
+#[test]
+fn destroy_is_deferred_until_on_destroy_ran() {
+    let scene = Scene::new();
+    let log = Log::default();
+    let entity = scene.instantiate();
+    entity
+        .add_component(Health::new(10.0))
+        .add_behaviour(Recorder {
+            name: "a",
+            log: log.clone(),
+        });
+    let mut executor = SystemExecutor::new();
+    executor.run(&scene, 0.1);
+    recorded(&log);
+
+    entity.destroy();
+    // Still alive until the end of the frame
+    assert!(scene.contains_entity(entity.id));
+
+    executor.run(&scene, 0.1);
+    assert_eq!(recorded(&log), ["a on_destroy Some(10.0)"]);
+    assert!(!scene.contains_entity(entity.id));
+
+    executor.run(&scene, 0.1);
+    assert!(recorded(&log).is_empty());
+}
+
+#[test]
+fn created_and_destroyed_in_one_frame_still_gets_on_destroy() {
+    let scene = Scene::new();
+    let log = Log::default();
+    let entity = scene.instantiate();
+    entity
+        .add_component(Health::new(5.0))
+        .add_behaviour(Recorder {
+            name: "a",
+            log: log.clone(),
+        });
+    entity.destroy();
+
+    SystemExecutor::new().run(&scene, 0.1);
+    assert_eq!(recorded(&log), ["a awake", "a on_destroy Some(5.0)"]);
+    assert!(!scene.contains_entity(entity.id));
+}
@@ -0,0 +1,101 @@
+/// Runtime borrow tracking is shared by every handle to an entity, on every thread
+use ecs_hybrid::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::thread;
+
+fn scene_with_health() -> (Scene, u64) {
+    let scene = Scene::new();
+    let entity = scene.instantiate();
+    entity.add_component(Health::new(100.0));
+    (scene, entity.id)
+}
+
+#[test]
+fn reads_through_different_handles_coexist() {
+    let (scene, id) = scene_with_health();
+    let first = scene.get_entity(id);
+    let second = scene.get_entity(id);
+
+    let a = first.get_component_raw::<Health>().unwrap();
+    let b = second.get_component_raw::<Health>().unwrap();
+    assert_eq!(a.current, b.current);
+}
+
+#[test]
+fn write_through_one_handle_blocks_reads_through_another() {
+    let (scene, id) = scene_with_health();
+    let first = scene.get_entity(id);
+    let second = scene.get_entity(id);
+
+    let write = first.get_component_raw_mut::<Health>().unwrap();
+    let read = catch_unwind(AssertUnwindSafe(|| {
+        second
+            .get_component_raw::<Health>()
+            .map(|health| health.current)
+    }));
+    assert!(read.is_err());
+    drop(write);
+
+    // Released with the guard
+    assert_eq!(
+        second
+            .get_component_raw::<Health>()
+            .map(|health| health.current),
+        Some(100.0)
+    );
+}
+
+#[test]
+fn read_through_one_handle_blocks_writes_through_another() {
+    let (scene, id) = scene_with_health();
+    let first = scene.get_entity(id);
+    let second = scene.get_entity(id);
+
+    let read = first.get_component_raw::<Health>().unwrap();
+    let write = catch_unwind(AssertUnwindSafe(|| {
+        second.get_component_raw_mut::<Health>().is_some()
+    }));
+    assert!(write.is_err());
+    drop(read);
+    assert!(second.get_component_raw_mut::<Health>().is_some());
+}
+
+#[test]
+fn borrows_are_seen_across_threads() {
+    let (scene, id) = scene_with_health();
+    let entity = scene.get_entity(id);
+    let other = scene.get_entity(id);
+
+    let write = entity.get_component_raw_mut::<Health>().unwrap();
+    let conflicting = thread::spawn(move || {
+        catch_unwind(AssertUnwindSafe(|| {
+            other.get_component_raw::<Health>().is_some()
+        }))
+        .is_err()
+    })
+    .join()
+    .unwrap();
+    assert!(conflicting);
+    drop(write);
+
+    let other = scene.get_entity(id);
+    let read = thread::spawn(move || other.get_component_raw::<Health>().map(|h| h.current))
+        .join()
+        .unwrap();
+    assert_eq!(read, Some(100.0));
+}
+
+#[test]
+fn other_component_types_are_tracked_separately() {
+    let (scene, id) = scene_with_health();
+    let entity = scene.get_entity(id);
+    entity.add_component(Name::new("Player"));
+
+    let _health = entity.get_component_raw_mut::<Health>().unwrap();
+    let other = scene.get_entity(id);
+    let name = other.get_component_raw_mut::<Name>();
+    assert_eq!(
+        name.map(|name| name.value.clone()).as_deref(),
+        Some("Player")
+    );
+}
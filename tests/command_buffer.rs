@@ -0,0 +1,39 @@
+/// Ids reserved by command buffers stay unique across World::restore and snapshot loading
+use ecs_hybrid::*;
+
+#[test]
+fn spawned_ids_survive_restore() {
+    let mut world = World::new();
+    world.create_entity();
+    let saved = world.snapshot();
+
+    let allocator = world.entity_allocator();
+    let mut commands = CommandBuffer::new();
+    let spawned = commands.spawn(&allocator);
+    commands.add_component(spawned, Health::new(10.0));
+
+    world.restore(&saved);
+    let created = world.create_entity();
+    assert_ne!(created, spawned);
+
+    commands.execute(&mut world);
+    assert!(world.contains_entity(created));
+    assert_eq!(
+        world.get_component::<Health>(spawned).unwrap().current,
+        10.0
+    );
+    assert!(world.get_component::<Health>(created).is_none());
+}
+
+#[test]
+fn spawned_ids_survive_loading_a_snapshot() {
+    let mut bytes = Vec::new();
+    World::new().write_snapshot(&mut bytes).unwrap();
+
+    let mut world = World::new();
+    let mut commands = CommandBuffer::new();
+    let spawned = commands.spawn(&world.entity_allocator());
+
+    world.read_snapshot(&mut bytes.as_slice()).unwrap();
+    assert_ne!(world.create_entity(), spawned);
+}
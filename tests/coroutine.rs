@@ -0,0 +1,82 @@
+/// Coroutine waits: frames, predicates and completion
+use ecs_hybrid::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn wait_frames_resumes_after_that_many_frames() {
+    let scene = Scene::new();
+    let entity = scene.instantiate();
+    let steps = Arc::new(AtomicUsize::new(0));
+    let counter = steps.clone();
+    scene.start_coroutine(&entity, move |_: &Entity, _: &Scene| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Yield::WaitFrames(3)
+    });
+
+    // First step on the first update
+    scene.update_coroutines(0.1);
+    assert_eq!(steps.load(Ordering::SeqCst), 1);
+    scene.update_coroutines(0.1);
+    scene.update_coroutines(0.1);
+    assert_eq!(steps.load(Ordering::SeqCst), 1);
+    scene.update_coroutines(0.1);
+    assert_eq!(steps.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn wait_until_resumes_on_the_first_frame_the_predicate_holds() {
+    let scene = Scene::new();
+    let entity = scene.instantiate();
+    let open = Arc::new(AtomicBool::new(false));
+    let steps = Arc::new(AtomicUsize::new(0));
+    let (gate, counter) = (open.clone(), steps.clone());
+    scene.start_coroutine(&entity, move |_: &Entity, _: &Scene| {
+        let gate = gate.clone();
+        if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+            Yield::WaitUntil(Box::new(move |_| gate.load(Ordering::SeqCst)))
+        } else {
+            Yield::Done
+        }
+    });
+
+    scene.update_coroutines(0.1);
+    scene.update_coroutines(0.1);
+    scene.update_coroutines(0.1);
+    assert_eq!(steps.load(Ordering::SeqCst), 1);
+
+    open.store(true, Ordering::SeqCst);
+    scene.update_coroutines(0.1);
+    assert_eq!(steps.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn done_drops_the_coroutine() {
+    let scene = Scene::new();
+    let entity = scene.instantiate();
+    let steps = Arc::new(AtomicUsize::new(0));
+    let counter = steps.clone();
+    scene.start_coroutine(&entity, move |_: &Entity, _: &Scene| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Yield::Done
+    });
+    assert_eq!(scene.coroutine_count(), 1);
+
+    scene.update_coroutines(0.1);
+    assert_eq!(scene.coroutine_count(), 0);
+    scene.update_coroutines(0.1);
+    assert_eq!(steps.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn destroying_the_entity_cancels_its_coroutines() {
+    let scene = Scene::new();
+    let entity = scene.instantiate();
+    scene.start_coroutine(&entity, |_: &Entity, _: &Scene| Yield::WaitFrames(1));
+    scene.update_coroutines(0.1);
+    assert_eq!(scene.coroutine_count(), 1);
+
+    entity.destroy();
+    scene.update_coroutines(0.1);
+    assert_eq!(scene.coroutine_count(), 0);
+}
@@ -0,0 +1,129 @@
+/// Schema migrations: a chain of steps run in order, for scenes and snapshots
+use ecs_hybrid::*;
+
+/// Armor as version 1 saved it
+#[derive(Debug, Clone, Default)]
+struct ArmorV1 {
+    value: f32,
+}
+impl_reflect!(ArmorV1 { value });
+
+/// Armor at version 3: v2 split `value` into `physical` and `magic`, v3 added `total`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Armor {
+    physical: f32,
+    magic: f32,
+    total: f32,
+}
+impl_reflect!(Armor {
+    physical,
+    magic,
+    total
+});
+
+fn armor_v1_to_v2(data: &mut ComponentData) {
+    data.rename("value", "physical");
+    data.set("magic", 5.0);
+}
+
+/// Only valid after v2 - reads both fields it introduced
+fn armor_v2_to_v3(data: &mut ComponentData) {
+    let field = |path| match data.get(path) {
+        Some(Value::Float(value)) => *value,
+        other => panic!("{} is {:?}", path, other),
+    };
+    let total = field("physical") + field("magic");
+    data.set("total", total);
+}
+
+fn v1_world() -> World {
+    let mut world = World::new();
+    world.registry_mut().set_id::<ArmorV1>("Armor");
+    world.registry_mut().register_serializable::<ArmorV1>();
+    world
+}
+
+fn v3_world() -> World {
+    let mut world = World::new();
+    let registry = world.registry_mut();
+    registry.set_id::<Armor>("Armor");
+    registry.register_serializable::<Armor>();
+    registry.set_version::<Armor>(3);
+    registry.add_migration::<Armor>(1, armor_v1_to_v2);
+    registry.add_migration::<Armor>(2, armor_v2_to_v3);
+    world
+}
+
+fn assert_chain_ran(report: &LoadReport) {
+    let steps: Vec<_> = report
+        .migrations
+        .iter()
+        .map(|m| (m.component.as_str(), m.from, m.to, m.instances))
+        .collect();
+    assert_eq!(steps, [("Armor", 1, 2, 2), ("Armor", 2, 3, 2)]);
+}
+
+#[test]
+fn scene_runs_every_step_in_order() {
+    let mut old = v1_world();
+    for value in [10.0, 20.0] {
+        let entity = old.create_entity();
+        old.add_component(entity, ArmorV1 { value });
+    }
+    let text = old.save_to_string().unwrap();
+
+    let mut world = v3_world();
+    let report = world.load_from_str(&text).unwrap();
+    assert_chain_ran(&report);
+    let armor = world.with_components::<Armor, _, _>(report.entities.map(1), <[_]>::to_vec);
+    assert_eq!(
+        armor,
+        Some(vec![Armor {
+            physical: 20.0,
+            magic: 5.0,
+            total: 25.0
+        }])
+    );
+}
+
+#[test]
+fn snapshot_runs_every_step_in_order() {
+    let mut old = v1_world();
+    let entity = old.create_entity();
+    old.add_component(entity, ArmorV1 { value: 10.0 });
+    old.add_component(entity, ArmorV1 { value: 1.0 });
+    let mut bytes = Vec::new();
+    old.write_snapshot(&mut bytes).unwrap();
+
+    let mut world = v3_world();
+    let report = world.read_snapshot(&mut bytes.as_slice()).unwrap();
+    assert_chain_ran(&report);
+    assert_eq!(
+        world.get_field(entity, "Armor.total"),
+        Ok(Value::Float(15.0))
+    );
+}
+
+#[test]
+fn missing_step_fails_the_load() {
+    let mut old = v1_world();
+    let entity = old.create_entity();
+    old.add_component(entity, ArmorV1 { value: 10.0 });
+    let text = old.save_to_string().unwrap();
+
+    let mut world = World::new();
+    let registry = world.registry_mut();
+    registry.set_id::<Armor>("Armor");
+    registry.register_serializable::<Armor>();
+    registry.set_version::<Armor>(3);
+    registry.add_migration::<Armor>(1, armor_v1_to_v2);
+
+    let error = world.load_from_str(&text).unwrap_err();
+    assert_eq!(error.component.as_deref(), Some("Armor"));
+    assert!(
+        error.message.contains("from schema version 2 to 3"),
+        "{}",
+        error
+    );
+    assert_eq!(world.entities().count(), 0);
+}
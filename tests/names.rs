@@ -0,0 +1,60 @@
+/// find_by_name follows Names changed in place, whichever mutable path changed them
+use ecs_hybrid::*;
+
+fn named(world: &mut World, name: &str) -> u64 {
+    let entity = world.create_entity();
+    world.add_component(entity, Name::new(name));
+    entity
+}
+
+#[test]
+fn write_component_rename_is_found() {
+    let mut world = World::new();
+    let entity = named(&mut world, "Old");
+    assert_eq!(world.find_by_name("Old"), Some(entity));
+
+    world.write_component::<Name>(entity).unwrap().value = "New".to_string();
+    assert_eq!(world.find_by_name("Old"), None);
+    assert_eq!(world.find_by_name("New"), Some(entity));
+}
+
+#[test]
+fn query_mut_rename_is_found() {
+    let mut world = World::new();
+    let first = named(&mut world, "a");
+    let second = named(&mut world, "b");
+
+    for (_, name) in world.query_mut::<Name>().unwrap() {
+        name.value = name.value.to_uppercase();
+    }
+    assert_eq!(world.find_by_name("A"), Some(first));
+    assert_eq!(world.find_by_name("B"), Some(second));
+    assert_eq!(world.find_by_name("a"), None);
+}
+
+#[test]
+fn entity_handle_renames_are_found() {
+    let scene = Scene::new();
+    let entity = scene.instantiate();
+    entity.add_component(Name::new("Player"));
+
+    entity.with_component_mut::<Name, _>(|name| name.value = "Hero".to_string());
+    assert_eq!(scene.find_by_name("Hero").map(|e| e.id), Some(entity.id));
+
+    entity.with::<&mut Name>(|name| name.value = "Villain".to_string());
+    assert_eq!(scene.find_by_name("Villain").map(|e| e.id), Some(entity.id));
+    assert!(scene.find_by_name("Hero").is_none());
+}
+
+#[test]
+fn index_survives_adds_and_removes_after_a_rebuild() {
+    let mut world = World::new();
+    let first = named(&mut world, "x");
+    world.write_component::<Name>(first).unwrap().value = "y".to_string();
+    assert_eq!(world.find_by_name("y"), Some(first));
+
+    let second = named(&mut world, "x");
+    assert_eq!(world.find_by_name("x"), Some(second));
+    world.remove_component::<Name>(first);
+    assert_eq!(world.find_by_name("y"), None);
+}
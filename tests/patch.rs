@@ -0,0 +1,96 @@
+/// World diffs: reparenting, despawning and respawning survive diff and apply
+use ecs_hybrid::*;
+
+/// Copy of a World that shares its entity ids, as `diff` needs
+fn copy(world: &World) -> World {
+    let mut copy = World::new();
+    copy.restore(&world.snapshot());
+    copy
+}
+
+/// Diff `from` -> `to`, send the patch through its binary form and apply it to `from`
+fn sync(from: &mut World, to: &World) {
+    let mut bytes = Vec::new();
+    from.diff(to).write(&mut bytes).unwrap();
+    from.apply_patch(&WorldPatch::read(&mut bytes.as_slice()).unwrap())
+        .unwrap();
+    assert_eq!(from.state_hash(), to.state_hash());
+    assert!(from.diff(to).is_empty());
+}
+
+#[test]
+fn parent_and_child_swap() {
+    let mut before = World::new();
+    let a = before.create_entity();
+    let b = before.create_entity();
+    let c = before.create_entity();
+    before.set_parent(b, Some(a));
+    before.set_parent(c, Some(b));
+
+    let mut after = copy(&before);
+    after.set_parent(b, None);
+    assert!(after.set_parent(a, Some(b)));
+
+    sync(&mut before, &after);
+    assert_eq!(before.parent(a), Some(b));
+    assert_eq!(before.parent(b), None);
+    assert_eq!(before.parent(c), Some(b));
+}
+
+#[test]
+fn cycle_is_rejected_before_anything_changes() {
+    let mut world = World::new();
+    let a = world.create_entity();
+    let b = world.create_entity();
+    world.set_parent(b, Some(a));
+    let tag = "Changed";
+
+    let mut patch = WorldPatch {
+        next_entity_id: 2,
+        ..WorldPatch::default()
+    };
+    patch.entities.push(EntityChange {
+        entity: a,
+        parent: Some(b),
+        active: true,
+        layers: LayerMask::DEFAULT,
+        tags: vec![tag.to_string()],
+    });
+    match world.apply_patch(&patch) {
+        Err(SnapshotError::Corrupt(message)) => assert!(message.contains("cycle"), "{}", message),
+        other => panic!("expected a cycle to be rejected, got {:?}", other),
+    }
+    assert_eq!(world.parent(b), Some(a));
+    assert_eq!(world.parent(a), None);
+    assert!(!world.has_tag(a, tag));
+}
+
+#[test]
+fn despawn_then_respawn() {
+    let mut alive = World::new();
+    let root = alive.create_entity();
+    let enemy = alive.create_entity();
+    let weapon = alive.create_entity();
+    alive.add_component(enemy, Name::new("Enemy"));
+    alive.add_component(enemy, Health::new(30.0));
+    alive.set_parent(enemy, Some(root));
+    alive.set_parent(weapon, Some(enemy));
+    alive.add_tag(enemy, "Boss");
+
+    let mut dead = copy(&alive);
+    dead.destroy_entity(enemy);
+    dead.set_parent(weapon, Some(root));
+
+    let mut world = copy(&alive);
+    sync(&mut world, &dead);
+    assert!(!world.contains_entity(enemy));
+    assert_eq!(world.find_by_name("Enemy"), None);
+
+    // Back to the earlier state - the destroyed id comes back with everything it held
+    sync(&mut world, &alive);
+    assert!(world.contains_entity(enemy));
+    assert_eq!(world.parent(enemy), Some(root));
+    assert_eq!(world.parent(weapon), Some(enemy));
+    assert_eq!(world.find_by_name("Enemy"), Some(enemy));
+    assert!(world.has_tag(enemy, "Boss"));
+}
@@ -0,0 +1,99 @@
+/// Replication: failed sends are retried, rejected packets change nothing on the client
+use ecs_hybrid::*;
+use std::io;
+
+/// Transport that fails while `down` is set and keeps what it sent
+#[derive(Default)]
+struct FlakyTransport {
+    down: bool,
+    sent: Vec<Vec<u8>>,
+}
+
+impl Transport for FlakyTransport {
+    fn send(&mut self, packet: Vec<u8>) -> io::Result<()> {
+        if self.down {
+            return Err(io::Error::new(io::ErrorKind::ConnectionReset, "down"));
+        }
+        self.sent.push(packet);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+fn replicated_world() -> World {
+    let mut world = World::new();
+    world.registry_mut().register_replicated::<Transform>();
+    world
+}
+
+#[test]
+fn failed_send_is_sent_again() {
+    let mut server_world = replicated_world();
+    let player = server_world.create_entity();
+    server_world.add_component(player, Transform::new(1.0, 2.0, 3.0));
+    let mut server = ReplicationServer::new();
+    let mut transport = FlakyTransport {
+        down: true,
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        server.send(&server_world, &mut transport),
+        Err(SnapshotError::Io(_))
+    ));
+    // Nothing changed since, but the lost delta still has to go out
+    transport.down = false;
+    assert!(server.send(&server_world, &mut transport).unwrap());
+    assert!(!server.send(&server_world, &mut transport).unwrap());
+
+    let mut client_world = replicated_world();
+    let mut client = ReplicationClient::new();
+    let patch = WorldPatch::read(&mut transport.sent[0].as_slice()).unwrap();
+    client.apply(&mut client_world, &patch).unwrap();
+    let local = client.client_entity(player).unwrap();
+    assert_eq!(
+        client_world.get_component::<Transform>(local).unwrap().x,
+        1.0
+    );
+}
+
+#[test]
+fn rejected_packet_leaves_client_untouched() {
+    let mut server_world = replicated_world();
+    let player = server_world.create_entity();
+    server_world.add_component(player, Transform::new(1.0, 2.0, 3.0));
+    let mut server = ReplicationServer::new();
+
+    let mut client_world = replicated_world();
+    let mut client = ReplicationClient::new();
+    client
+        .apply(&mut client_world, &server.delta(&server_world))
+        .unwrap();
+    let local = client.client_entity(player).unwrap();
+
+    // A new entity, with a component type the client does not know
+    let enemy = server_world.create_entity();
+    server_world.add_component(enemy, Transform::default());
+    let mut patch = server.delta(&server_world);
+    patch.components[0].component = "Unknown".to_string();
+    match client.apply(&mut client_world, &patch) {
+        Err(SnapshotError::UnknownComponent(id)) => assert_eq!(id, "Unknown"),
+        other => panic!("expected an unknown component, got {:?}", other),
+    }
+    // A change to an entity the client never saw
+    patch.components[0].component = "Transform".to_string();
+    patch.spawned.clear();
+    patch.entities.clear();
+    match client.apply(&mut client_world, &patch) {
+        Err(SnapshotError::MissingEntity(id)) => assert_eq!(id, enemy),
+        other => panic!("expected a missing entity, got {:?}", other),
+    }
+
+    assert_eq!(client.client_entity(enemy), None);
+    assert_eq!(client_world.entities().collect::<Vec<_>>(), [local]);
+    // No id was reserved for the rejected spawn
+    assert_eq!(client_world.create_entity(), local + 1);
+}
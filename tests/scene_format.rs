@@ -0,0 +1,32 @@
+/// Scene text format: scripts left out of saves, enabled flags shared by every instance
+use ecs_hybrid::*;
+
+struct Idle;
+
+impl Behaviour for Idle {}
+
+#[test]
+fn saving_skips_scripts() {
+    let scene = Scene::new();
+    let player = scene.instantiate();
+    player.add_component(Name::new("Player"));
+    player.add_behaviour(Idle);
+
+    let text = scene.save_to_string().unwrap();
+    let loaded = Scene::new();
+    loaded.load_from_str(&text).unwrap();
+    let player = loaded.find("Player").unwrap();
+    assert!(player.with_component::<Name, _>(|_| ()).is_some());
+}
+
+#[test]
+fn mixed_enabled_flags_are_rejected() {
+    let text = "ecs_hybrid scene 1\n\
+                entity 3\n\
+                \x20 component Health\n\
+                \x20 component Health disabled\n";
+    let error = Scene::new().load_from_str(text).unwrap_err();
+    assert_eq!(error.line, 4);
+    assert_eq!(error.entity, Some(3));
+    assert_eq!(error.component.as_deref(), Some("Health"));
+}
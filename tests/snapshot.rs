@@ -0,0 +1,214 @@
+/// Binary snapshot round trip and rejection of damaged input
+use ecs_hybrid::*;
+
+/// A few entities covering everything a snapshot stores
+fn build_world() -> World {
+    let mut world = World::new();
+    let root = world.create_entity();
+    world.add_component(root, Name::new("Root"));
+
+    let mover = world.create_entity();
+    world.add_component(mover, Transform::new(1.0, -0.5, 0.25));
+    world.add_component(mover, Velocity::new(1.0, 0.0, -1.0));
+
+    // Multi-instance component, quoted name, parent
+    let tank = world.create_entity();
+    world.add_component(tank, Health::new(100.0));
+    world.add_component(tank, Health::new(2.0));
+    world.add_component(tank, Name::new("Tank \"2\""));
+    world.set_parent(tank, Some(root));
+
+    // Inactive, disabled component, tag, layers
+    let sleeper = world.create_entity();
+    world.add_component(sleeper, Transform::new(3.0, 0.0, 0.0));
+    world.set_component_enabled::<Transform>(sleeper, false);
+    world.set_active(sleeper, false);
+    world.add_tag(sleeper, "Sleeping");
+    world.set_layers(sleeper, LayerMask::layer(4));
+    world
+}
+
+fn write(world: &World) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    world.write_snapshot(&mut bytes).unwrap();
+    bytes
+}
+
+/// Compare everything a snapshot stores
+fn assert_same(a: &World, b: &World) {
+    assert_eq!(
+        a.entities().collect::<Vec<_>>(),
+        b.entities().collect::<Vec<_>>()
+    );
+    for entity in a.entities() {
+        assert_eq!(a.parent(entity), b.parent(entity));
+        assert_eq!(a.is_active_self(entity), b.is_active_self(entity));
+        assert_eq!(a.layers(entity), b.layers(entity));
+        assert_eq!(a.tags(entity), b.tags(entity));
+        assert_eq!(
+            a.is_component_enabled::<Transform>(entity),
+            b.is_component_enabled::<Transform>(entity)
+        );
+        assert_eq!(
+            a.with_components::<Transform, _, _>(entity, <[_]>::to_vec),
+            b.with_components::<Transform, _, _>(entity, <[_]>::to_vec)
+        );
+        assert_eq!(
+            a.with_components::<Velocity, _, _>(entity, <[_]>::to_vec),
+            b.with_components::<Velocity, _, _>(entity, <[_]>::to_vec)
+        );
+        assert_eq!(
+            a.with_components::<Health, _, _>(entity, <[_]>::to_vec),
+            b.with_components::<Health, _, _>(entity, <[_]>::to_vec)
+        );
+        assert_eq!(
+            a.with_components::<Name, _, _>(entity, <[_]>::to_vec),
+            b.with_components::<Name, _, _>(entity, <[_]>::to_vec)
+        );
+    }
+}
+
+#[test]
+fn round_trip() {
+    let world = build_world();
+    let bytes = write(&world);
+
+    let mut loaded = World::new();
+    let report = loaded.read_snapshot(&mut bytes.as_slice()).unwrap();
+
+    assert!(report.migrations.is_empty());
+    assert_same(&world, &loaded);
+    assert_eq!(
+        loaded.with_components::<Health, _, _>(2, |h| h.len()),
+        Some(2)
+    );
+    assert_eq!(loaded.find_by_name("Tank \"2\""), Some(2));
+    assert!(!loaded.is_component_enabled::<Transform>(3));
+    assert_eq!(loaded.create_entity(), 4);
+}
+
+#[test]
+fn flipped_payload_byte_is_a_checksum_mismatch() {
+    let mut bytes = write(&build_world());
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+
+    // The world is left untouched
+    let mut world = build_world();
+    match world.read_snapshot(&mut bytes.as_slice()) {
+        Err(SnapshotError::ChecksumMismatch { expected, found }) => assert_ne!(expected, found),
+        other => panic!("expected a checksum mismatch, got {:?}", other),
+    }
+    assert_same(&build_world(), &world);
+}
+
+#[test]
+fn wrong_magic_is_not_a_snapshot() {
+    let mut bytes = write(&build_world());
+    bytes[0] = b'X';
+    match World::new().read_snapshot(&mut bytes.as_slice()) {
+        Err(SnapshotError::NotASnapshot) => {}
+        other => panic!("expected NotASnapshot, got {:?}", other),
+    }
+}
+
+#[test]
+fn newer_format_version_is_unsupported() {
+    let mut bytes = write(&build_world());
+    // Format version follows the 8 magic bytes
+    bytes[8..12].copy_from_slice(&99u32.to_le_bytes());
+    match World::new().read_snapshot(&mut bytes.as_slice()) {
+        Err(SnapshotError::UnsupportedVersion(99)) => {}
+        other => panic!("expected UnsupportedVersion(99), got {:?}", other),
+    }
+}
+
+#[test]
+fn truncated_input_is_rejected() {
+    let bytes = write(&build_world());
+    match World::new().read_snapshot(&mut &bytes[..bytes.len() / 2]) {
+        Err(SnapshotError::Corrupt(_)) => {}
+        other => panic!("expected a truncated payload error, got {:?}", other),
+    }
+    // Not even a whole header
+    match World::new().read_snapshot(&mut &bytes[..10]) {
+        Err(SnapshotError::Io(_)) => {}
+        other => panic!("expected an I/O error, got {:?}", other),
+    }
+}
+
+/// Frame a hand-written payload the way `write_snapshot` does
+fn framed(payload: &[u8]) -> Vec<u8> {
+    // 64-bit FNV-1a
+    let checksum = payload.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    let mut bytes = b"ECSHSNAP".to_vec();
+    bytes.extend(2u32.to_le_bytes());
+    bytes.extend(0u32.to_le_bytes());
+    bytes.extend((payload.len() as u64).to_le_bytes());
+    bytes.extend(checksum.to_le_bytes());
+    bytes.extend(payload);
+    bytes
+}
+
+/// Payload of `(id, parent)` entities, plus a Health (with default fields) on each of `health`
+fn payload(next_entity_id: u64, entities: &[(u64, Option<u64>)], health: &[u64]) -> Vec<u8> {
+    let mut bytes = next_entity_id.to_le_bytes().to_vec();
+    bytes.extend((entities.len() as u32).to_le_bytes());
+    for &(id, parent) in entities {
+        bytes.extend(id.to_le_bytes());
+        match parent {
+            Some(parent) => {
+                bytes.push(2);
+                bytes.extend(parent.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+    }
+    if health.is_empty() {
+        bytes.extend(0u32.to_le_bytes());
+        return bytes;
+    }
+    // One type: "Health", schema version 1, no fields
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend(6u32.to_le_bytes());
+    bytes.extend(b"Health");
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend(0u32.to_le_bytes());
+    bytes.extend((health.len() as u32).to_le_bytes());
+    for &id in health {
+        bytes.extend(id.to_le_bytes());
+        bytes.push(1);
+        bytes.extend(1u32.to_le_bytes());
+    }
+    bytes
+}
+
+#[test]
+fn inconsistent_entity_tables_are_corrupt() {
+    let valid = framed(&payload(3, &[(0, None), (2, Some(0))], &[2]));
+    let mut world = World::new();
+    world.read_snapshot(&mut valid.as_slice()).unwrap();
+    assert_eq!(world.parent(2), Some(0));
+    assert!(world.is_component_enabled::<Health>(2));
+
+    let cases = [
+        (payload(1, &[(5, None)], &[]), "next entity id"),
+        (payload(3, &[(0, None), (0, None)], &[]), "twice"),
+        (payload(3, &[(0, Some(2))], &[]), "parent 2"),
+        (
+            payload(3, &[(0, Some(1)), (1, Some(0)), (2, Some(1))], &[]),
+            "loops",
+        ),
+        (payload(3, &[(0, None)], &[2]), "entity 2"),
+    ];
+    for (payload, reason) in cases {
+        match World::new().read_snapshot(&mut framed(&payload).as_slice()) {
+            Err(SnapshotError::Corrupt(message)) => {
+                assert!(message.contains(reason), "{}", message)
+            }
+            other => panic!("expected a corrupt snapshot ({}), got {:?}", reason, other),
+        }
+    }
+}